@@ -0,0 +1,372 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::settings::Budgets;
+
+#[cfg(feature = "app")]
+use crate::{
+    cache::EntryCache,
+    parser::{
+        aggregate_usage,
+        range::{ReportTimeZone, TimeRange},
+    },
+    settings::SettingsStore,
+};
+#[cfg(feature = "app")]
+use chrono::{DateTime, Utc};
+#[cfg(feature = "app")]
+use tauri::{AppHandle, Manager};
+#[cfg(feature = "app")]
+use tauri_plugin_notification::NotificationExt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BudgetPeriod {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl BudgetPeriod {
+    fn name(self) -> &'static str {
+        match self {
+            BudgetPeriod::Daily => "daily",
+            BudgetPeriod::Weekly => "weekly",
+            BudgetPeriod::Monthly => "monthly",
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            BudgetPeriod::Daily => "today",
+            BudgetPeriod::Weekly => "this week",
+            BudgetPeriod::Monthly => "this month",
+        }
+    }
+
+    /// First day of the calendar period containing `today`, weeks starting
+    /// on Monday
+    fn start(self, today: NaiveDate) -> NaiveDate {
+        match self {
+            BudgetPeriod::Daily => today,
+            BudgetPeriod::Weekly => {
+                today - Duration::days(today.weekday().num_days_from_monday() as i64)
+            }
+            BudgetPeriod::Monthly => today.with_day(1).unwrap_or(today),
+        }
+    }
+
+    fn budget(self, budgets: &Budgets) -> Option<f64> {
+        match self {
+            BudgetPeriod::Daily => budgets.daily_usd,
+            BudgetPeriod::Weekly => budgets.weekly_usd,
+            BudgetPeriod::Monthly => budgets.monthly_usd,
+        }
+    }
+}
+
+const PERIODS: [BudgetPeriod; 3] = [
+    BudgetPeriod::Daily,
+    BudgetPeriod::Weekly,
+    BudgetPeriod::Monthly,
+];
+
+/// A budget threshold crossed for the first time in its period
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetAlert {
+    pub period: BudgetPeriod,
+    pub percent: u32,
+    pub spent: f64,
+    pub budget: f64,
+}
+
+impl BudgetAlert {
+    pub fn title(&self) -> String {
+        format!(
+            "Claude {} budget {}% used",
+            self.period.name(),
+            self.percent
+        )
+    }
+
+    pub fn body(&self) -> String {
+        format!(
+            "${:.2} of ${:.2} spent {}",
+            self.spent,
+            self.budget,
+            self.period.describe()
+        )
+    }
+}
+
+/// Budget alerts already shown, keyed "<period>:<period start>:<percent>"
+/// and kept on disk so a restart doesn't repeat them
+pub struct AlertState {
+    fired: Mutex<HashSet<String>>,
+    path: Option<PathBuf>,
+}
+
+impl Default for AlertState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlertState {
+    pub fn new() -> Self {
+        Self {
+            fired: Mutex::new(HashSet::new()),
+            path: None,
+        }
+    }
+
+    /// Restore fired alerts from `path` and write them back there on change
+    pub fn with_file(path: PathBuf) -> Self {
+        let fired = match load_fired(&path) {
+            Ok(fired) => fired,
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    eprintln!("Ignoring alert state {:?}: {:?}", path, e);
+                }
+                HashSet::new()
+            }
+        };
+
+        Self {
+            fired: Mutex::new(fired),
+            path: Some(path),
+        }
+    }
+
+    /// Record the thresholds crossed in the day, week and month containing
+    /// `today`, forgetting alerts from earlier periods. `spent_since` gives
+    /// the cost from a period's first day on. Returns the highest threshold
+    /// newly crossed in each period.
+    pub fn record_crossed(
+        &self,
+        budgets: &Budgets,
+        today: NaiveDate,
+        spent_since: impl Fn(NaiveDate) -> f64,
+    ) -> Vec<BudgetAlert> {
+        let mut thresholds = budgets.thresholds.clone();
+        thresholds.sort_unstable();
+        thresholds.dedup();
+
+        let mut fired = self.fired.lock().unwrap();
+        let before = fired.len();
+
+        // Alerts from earlier periods can never fire again
+        let current: Vec<String> = PERIODS
+            .iter()
+            .map(|p| format!("{}:{}:", p.name(), p.start(today)))
+            .collect();
+        fired.retain(|key| current.iter().any(|prefix| key.starts_with(prefix)));
+        let mut changed = fired.len() != before;
+
+        let mut alerts = Vec::new();
+        for (&period, prefix) in PERIODS.iter().zip(&current) {
+            let Some(budget) = period.budget(budgets) else {
+                continue;
+            };
+            let spent = spent_since(period.start(today));
+
+            let mut highest_new = None;
+            for &percent in &thresholds {
+                if spent < budget * percent as f64 / 100.0 {
+                    break;
+                }
+                if fired.insert(format!("{}{}", prefix, percent)) {
+                    highest_new = Some(percent);
+                    changed = true;
+                }
+            }
+
+            if let Some(percent) = highest_new {
+                alerts.push(BudgetAlert {
+                    period,
+                    percent,
+                    spent,
+                    budget,
+                });
+            }
+        }
+
+        if changed {
+            self.save(&fired);
+        }
+        alerts
+    }
+
+    fn save(&self, fired: &HashSet<String>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Err(e) = write_fired(path, fired) {
+            eprintln!("Failed to write alert state {:?}: {:?}", path, e);
+        }
+    }
+}
+
+fn load_fired(path: &Path) -> io::Result<HashSet<String>> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content).map_err(io::Error::from)
+}
+
+fn write_fired(path: &Path, fired: &HashSet<String>) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut keys: Vec<&String> = fired.iter().collect();
+    keys.sort();
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, serde_json::to_string(&keys)?)?;
+    fs::rename(&tmp_path, path)
+}
+
+/// Notify about each budget threshold crossed for the first time in the
+/// current day, week or month. When several are crossed at once only the
+/// highest is shown.
+#[cfg(feature = "app")]
+pub fn check_budgets(app: &AppHandle) {
+    let budgets = app.state::<SettingsStore>().get().budgets;
+    if PERIODS.iter().all(|p| p.budget(&budgets).is_none()) {
+        return;
+    }
+
+    let tz = ReportTimeZone::Local;
+    let entries = app.state::<EntryCache>().get_all_entries();
+    let spent_since = |start: NaiveDate| {
+        let range = TimeRange {
+            start: tz.start_of_day(start),
+            end: DateTime::<Utc>::MAX_UTC,
+            tz,
+        };
+        aggregate_usage(&range.filter(&entries)).total_cost_usd
+    };
+
+    let state = app.state::<AlertState>();
+    for alert in state.record_crossed(&budgets, tz.today(), spent_since) {
+        let shown = app
+            .notification()
+            .builder()
+            .title(alert.title())
+            .body(alert.body())
+            .show();
+        if let Err(e) = shown {
+            eprintln!("Failed to show budget notification: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    fn budgets(daily: Option<f64>, weekly: Option<f64>, monthly: Option<f64>) -> Budgets {
+        Budgets {
+            daily_usd: daily,
+            weekly_usd: weekly,
+            monthly_usd: monthly,
+            thresholds: vec![100, 50, 80],
+        }
+    }
+
+    /// (period, percent) of each alert
+    fn fired(alerts: Vec<BudgetAlert>) -> Vec<(BudgetPeriod, u32)> {
+        alerts.into_iter().map(|a| (a.period, a.percent)).collect()
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // (day, first day of its week)
+        let cases = [
+            ("2025-06-01", "2025-05-26"), // Sunday
+            ("2025-06-02", "2025-06-02"), // Monday
+            ("2025-06-04", "2025-06-02"),
+            ("2025-01-01", "2024-12-30"), // across a year
+        ];
+
+        for (today, monday) in cases {
+            assert_eq!(
+                BudgetPeriod::Weekly.start(day(today)),
+                day(monday),
+                "{}",
+                today
+            );
+        }
+    }
+
+    #[test]
+    fn crossing_several_thresholds_notifies_the_highest() {
+        let state = AlertState::new();
+        let budgets = budgets(Some(10.0), None, None);
+        let today = day("2025-06-04");
+
+        // (spent, expected new alerts)
+        let steps: [(f64, &[(BudgetPeriod, u32)]); 4] = [
+            (4.0, &[]),
+            (9.0, &[(BudgetPeriod::Daily, 80)]),
+            (9.5, &[]),
+            (25.0, &[(BudgetPeriod::Daily, 100)]),
+        ];
+        for (spent, expected) in steps {
+            let alerts = state.record_crossed(&budgets, today, |_| spent);
+            assert_eq!(fired(alerts), expected, "spent {}", spent);
+        }
+
+        let fresh = AlertState::new();
+        let alerts = fresh.record_crossed(&budgets, today, |_| 12.0);
+        assert_eq!(fired(alerts), [(BudgetPeriod::Daily, 100)]);
+    }
+
+    #[test]
+    fn fired_alerts_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fired-alerts.json");
+        let budgets = budgets(Some(10.0), None, None);
+        let today = day("2025-06-04");
+
+        let state = AlertState::with_file(path.clone());
+        let alerts = state.record_crossed(&budgets, today, |_| 8.0);
+        assert_eq!(fired(alerts), [(BudgetPeriod::Daily, 80)]);
+
+        let restarted = AlertState::with_file(path);
+        assert!(restarted
+            .record_crossed(&budgets, today, |_| 8.0)
+            .is_empty());
+        let alerts = restarted.record_crossed(&budgets, today, |_| 10.0);
+        assert_eq!(fired(alerts), [(BudgetPeriod::Daily, 100)]);
+    }
+
+    #[test]
+    fn alerts_rearm_when_a_period_starts() {
+        let budgets = budgets(Some(10.0), Some(10.0), Some(10.0));
+        let state = AlertState::new();
+        let all = [
+            (BudgetPeriod::Daily, 100),
+            (BudgetPeriod::Weekly, 100),
+            (BudgetPeriod::Monthly, 100),
+        ];
+
+        // (today, expected alerts), always over every budget
+        let steps: [(&str, &[(BudgetPeriod, u32)]); 5] = [
+            ("2025-06-28", &all),                          // Saturday
+            ("2025-06-28", &[]),                           // same day
+            ("2025-06-29", &[(BudgetPeriod::Daily, 100)]), // Sunday, same week
+            ("2025-06-30", &all[..2]),                     // Monday, same month
+            ("2025-07-01", &[all[0], all[2]]),             // new month, same week
+        ];
+        for (today, expected) in steps {
+            let alerts = state.record_crossed(&budgets, day(today), |_| 50.0);
+            assert_eq!(fired(alerts), expected, "{}", today);
+        }
+    }
+}
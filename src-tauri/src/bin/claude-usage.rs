@@ -0,0 +1,348 @@
+//! Headless report tool sharing the parser and pricing code with the menu bar app.
+//!
+//! Usage: claude-usage <report|windows|sessions|projects> [--range today|week|month|all]
+//!        [--start DATE --end DATE] [--tz ZONE] [--plan PLAN] [--log-dir DIR]
+//!        [--format table|json]
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use claude_usage_menubar_lib::parser::{
+    aggregate_usage,
+    blocks::{billing_windows_for_range, Plan},
+    dedupe_entries, find_jsonl_files, get_project_breakdown, get_session_breakdown,
+    parse_jsonl_file,
+    range::{ReportTimeZone, TimeRange},
+    roots::discover_log_roots,
+    types::{BillingWindow, LogEntry, ProjectSummary, SessionSummary, UsageData},
+};
+use claude_usage_menubar_lib::pricing::reload_user_pricing;
+
+const USAGE: &str = "\
+Usage: claude-usage <COMMAND> [OPTIONS]
+
+Commands:
+  report      Cost and token totals with a per-model breakdown
+  windows     5-hour billing windows
+  sessions    Per-session summary
+  projects    Per-project summary
+
+Options:
+  --range <today|week|month|all>   Time range to report on [default: today]
+  --start <DATE> --end <DATE>      Explicit range instead of --range. RFC 3339
+                                   or YYYY-MM-DD; an end date is inclusive
+  --tz <ZONE>                      IANA time zone for calendar days [default: local]
+  --plan <pro|max5|max20>          Plan whose cap windows are measured against
+                                   [default: the busiest past block]
+  --token-limit <TOKENS>           Custom per-block token cap instead of --plan
+  --log-dir <DIR>                  Also read logs from DIR; may be repeated.
+                                   CLAUDE_CONFIG_DIR is honored as well
+  --format <table|json>            Output format [default: table]
+  -h, --help                       Print this help";
+
+#[derive(Clone, Copy)]
+enum Command {
+    Report,
+    Windows,
+    Sessions,
+    Projects,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Table,
+    Json,
+}
+
+struct Args {
+    command: Command,
+    range: String,
+    start: Option<String>,
+    end: Option<String>,
+    tz: Option<String>,
+    plan: Option<Plan>,
+    log_dirs: Vec<PathBuf>,
+    format: Format,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut command = None;
+    let mut range = "today".to_string();
+    let mut start = None;
+    let mut end = None;
+    let mut tz = None;
+    let mut plan = None;
+    let mut log_dirs = Vec::new();
+    let mut format = Format::Table;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--range" => {
+                let value = args.next().ok_or("--range requires a value")?;
+                if !matches!(value.as_str(), "today" | "week" | "month" | "all") {
+                    return Err(format!("Unknown range: {}", value));
+                }
+                range = value;
+            }
+            "--start" => start = Some(args.next().ok_or("--start requires a value")?),
+            "--end" => end = Some(args.next().ok_or("--end requires a value")?),
+            "--tz" => tz = Some(args.next().ok_or("--tz requires a value")?),
+            "--plan" => {
+                let value = args.next().ok_or("--plan requires a value")?;
+                plan = Some(Plan::parse(&value).ok_or(format!("Unknown plan: {}", value))?);
+            }
+            "--token-limit" => {
+                let value = args.next().ok_or("--token-limit requires a value")?;
+                let token_limit = value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or(format!("Invalid token limit: {}", value))?;
+                plan = Some(Plan::Custom {
+                    token_limit: Some(token_limit),
+                    cost_limit_usd: None,
+                });
+            }
+            "--log-dir" => {
+                log_dirs.push(PathBuf::from(
+                    args.next().ok_or("--log-dir requires a value")?,
+                ));
+            }
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("table") => Format::Table,
+                    Some("json") => Format::Json,
+                    Some(other) => return Err(format!("Unknown format: {}", other)),
+                    None => return Err("--format requires a value".to_string()),
+                };
+            }
+            "report" if command.is_none() => command = Some(Command::Report),
+            "windows" if command.is_none() => command = Some(Command::Windows),
+            "sessions" if command.is_none() => command = Some(Command::Sessions),
+            "projects" if command.is_none() => command = Some(Command::Projects),
+            other => return Err(format!("Unexpected argument: {}", other)),
+        }
+    }
+
+    let command = command.ok_or("Missing command")?;
+    if start.is_some() != end.is_some() {
+        return Err("--start and --end must be given together".to_string());
+    }
+
+    Ok(Some(Args {
+        command,
+        range,
+        start,
+        end,
+        tz,
+        plan,
+        log_dirs,
+        format,
+    }))
+}
+
+fn load_entries(log_dirs: &[PathBuf]) -> Result<Vec<LogEntry>, String> {
+    let roots = discover_log_roots(log_dirs);
+    roots.report_missing();
+    if roots.found.is_empty() {
+        return Err("No Claude project paths found".to_string());
+    }
+
+    let entries = find_jsonl_files(&roots.found)
+        .iter()
+        .flat_map(|path| parse_jsonl_file(path))
+        .collect();
+    Ok(dedupe_entries(entries))
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn print_usage_table(usage: &UsageData) {
+    let tokens = &usage.total_tokens;
+    println!("Total cost:    ${:.2}", usage.total_cost_usd);
+    println!("Input:         {}", tokens.input_tokens);
+    println!("Output:        {}", tokens.output_tokens);
+    println!(
+        "Cache create:  {} (5m: {}, 1h: {})",
+        tokens.cache_creation_input_tokens,
+        tokens.cache_creation_5m_input_tokens,
+        tokens.cache_creation_1h_input_tokens
+    );
+    println!("Cache read:    {}", tokens.cache_read_input_tokens);
+    println!();
+
+    let mut models = usage.model_breakdown.clone();
+    models.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+
+    println!(
+        "{:<32} {:>12} {:>12} {:>14} {:>14} {:>10}",
+        "Model", "Input", "Output", "Cache create", "Cache read", "Cost"
+    );
+    for m in &models {
+        println!(
+            "{:<32} {:>12} {:>12} {:>14} {:>14} {:>10}",
+            m.model,
+            m.input_tokens,
+            m.output_tokens,
+            m.cache_creation_input_tokens,
+            m.cache_read_input_tokens,
+            format!("${:.2}", m.cost_usd)
+        );
+    }
+
+    if !usage.unpriced_models.is_empty() {
+        println!();
+        println!(
+            "Cost is an estimate: no price is known for these models, Sonnet rates were used."
+        );
+        for m in &usage.unpriced_models {
+            let t = &m.tokens;
+            let total = t.input_tokens
+                + t.output_tokens
+                + t.cache_creation_input_tokens
+                + t.cache_read_input_tokens;
+            println!(
+                "  {} ({} tokens, ~${:.2})",
+                m.model, total, m.estimated_cost_usd
+            );
+        }
+    }
+}
+
+fn print_windows_table(windows: &[BillingWindow]) {
+    println!(
+        "{:<27} {:<27} {:>14} {:>10} {:>8} {:>10}",
+        "Start", "End", "Tokens", "Cost", "Limit", "Remaining"
+    );
+    for w in windows {
+        let percent = w
+            .percent_used
+            .map_or_else(|| "-".to_string(), |p| format!("{:.1}%", p));
+        let remaining = if w.is_gap {
+            "idle".to_string()
+        } else if w.is_active {
+            format!("{}m", w.remaining_minutes)
+        } else {
+            "-".to_string()
+        };
+        println!(
+            "{:<27} {:<27} {:>14} {:>10} {:>8} {:>10}",
+            w.start_time,
+            w.end_time,
+            w.total_tokens,
+            format!("${:.2}", w.cost_usd),
+            percent,
+            remaining
+        );
+    }
+
+    for w in windows.iter().filter(|w| w.is_active) {
+        if let (Some(rate), Some(projection)) = (&w.burn_rate, &w.projection) {
+            println!();
+            println!(
+                "Burn rate: {:.0} tokens/min, ${:.2}/h. At block end: {} tokens, ${:.2}",
+                rate.tokens_per_minute,
+                rate.cost_per_hour,
+                projection.total_tokens,
+                projection.cost_usd
+            );
+        }
+        if let Some(ref at) = w.predicted_limit_time {
+            println!("Limit reached at {} at this pace", at);
+        }
+    }
+}
+
+fn print_sessions_table(sessions: &[SessionSummary]) {
+    println!("{:<38} {:>9} {:>10}  Project", "Session", "Messages", "Cost");
+    for s in sessions {
+        println!(
+            "{:<38} {:>9} {:>10}  {}",
+            s.session_id,
+            s.message_count,
+            format!("${:.2}", s.total_cost_usd),
+            s.project_path
+        );
+    }
+}
+
+fn print_projects_table(projects: &[ProjectSummary]) {
+    println!(
+        "{:>9} {:>9} {:>10}  {:<27}  Project",
+        "Sessions", "Messages", "Cost", "Last activity"
+    );
+    for p in projects {
+        println!(
+            "{:>9} {:>9} {:>10}  {:<27}  {}",
+            p.session_count,
+            p.message_count,
+            format!("${:.2}", p.total_cost_usd),
+            p.last_activity,
+            p.project_path
+        );
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    reload_user_pricing().map_err(|e| e.to_string())?;
+    let tz = ReportTimeZone::parse(args.tz.as_deref()).map_err(|e| e.to_string())?;
+    let range = match (&args.start, &args.end) {
+        (Some(start), Some(end)) => TimeRange::parse(start, end, tz).map_err(|e| e.to_string())?,
+        _ => TimeRange::preset(&args.range, tz),
+    };
+
+    let entries = load_entries(&args.log_dirs)?;
+    let filtered = range.filter(&entries);
+
+    match (args.command, args.format) {
+        (Command::Report, Format::Json) => print_json(&aggregate_usage(&filtered))?,
+        (Command::Report, Format::Table) => print_usage_table(&aggregate_usage(&filtered)),
+        (Command::Windows, Format::Json) => print_json(&billing_windows_for_range(
+            &entries,
+            &range,
+            args.plan.as_ref(),
+        ))?,
+        (Command::Windows, Format::Table) => print_windows_table(&billing_windows_for_range(
+            &entries,
+            &range,
+            args.plan.as_ref(),
+        )),
+        (Command::Sessions, Format::Json) => print_json(&get_session_breakdown(&filtered))?,
+        (Command::Sessions, Format::Table) => {
+            print_sessions_table(&get_session_breakdown(&filtered))
+        }
+        (Command::Projects, Format::Json) => print_json(&get_project_breakdown(&filtered))?,
+        (Command::Projects, Format::Table) => {
+            print_projects_table(&get_project_breakdown(&filtered))
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
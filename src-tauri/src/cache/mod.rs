@@ -1,20 +1,117 @@
-use std::collections::HashMap;
-use std::fs;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File, Metadata, Permissions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::RwLock;
 use std::time::SystemTime;
 
-use crate::parser::{find_jsonl_files, get_claude_paths, parse_jsonl_file, types::LogEntry};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use crate::parser::{
+    dedupe_entries, find_jsonl_files, parse_jsonl_from,
+    project::ProjectResolver,
+    types::{FileDiagnostics, LogEntry, ParseStats},
+};
+
+/// Bump whenever `CachedFile` or `LogEntry` change shape so stale snapshots
+/// are discarded instead of half-deserialized.
+const SNAPSHOT_VERSION: u32 = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CachedFile {
     pub path: PathBuf,
     pub modified_time: SystemTime,
     pub entries: Vec<LogEntry>,
+    /// File size when last read
+    pub size: u64,
+    /// Byte offset just past the last complete line parsed
+    pub offset: u64,
+    /// Inode (or platform equivalent) used to detect a replaced file
+    pub file_id: Option<u64>,
+    /// Lines read and skipped across every parse since `offset` was 0
+    pub stats: ParseStats,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    files: Vec<CachedFile>,
+}
+
+/// What a rescan or invalidation changed, reported to the dashboard
+#[derive(Default)]
+pub struct CacheDelta {
+    /// Entries appended to the logs, including copies of messages already
+    /// read from another file
+    pub new_entries: usize,
+    pub session_ids: BTreeSet<String>,
+    pub project_paths: BTreeSet<String>,
+    /// A file was truncated, replaced or deleted, so entries may have gone
+    /// as well as arrived
+    pub reset: bool,
+    projects: ProjectResolver,
+}
+
+impl CacheDelta {
+    fn record(&mut self, entries: &[LogEntry]) {
+        self.new_entries += entries.len();
+        for entry in entries {
+            let session_id = entry.session_id.as_deref().unwrap_or("unknown");
+            self.session_ids.insert(session_id.to_string());
+            self.project_paths.insert(self.projects.project_path(entry));
+        }
+    }
+
+    /// Record `cached` as loaded over `previous_len` earlier entries, the
+    /// new ones starting at `start`
+    fn record_load(&mut self, cached: &CachedFile, start: usize, previous_len: usize) {
+        self.reset |= start < previous_len;
+        self.record(&cached.entries[start..]);
+    }
+
+    /// A change that affects every entry, such as new prices
+    pub fn everything() -> Self {
+        Self {
+            reset: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// What a file's metadata looked like, so an unreadable file is only
+/// retried once it is written to or its permissions change
+type FileStamp = (Option<SystemTime>, u64, Permissions);
+
+fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((
+        metadata.modified().ok(),
+        metadata.len(),
+        metadata.permissions(),
+    ))
+}
+
+struct UnreadableFile {
+    error: String,
+    stamp: Option<FileStamp>,
+}
+
+impl UnreadableFile {
+    fn new(path: &Path, error: io::Error) -> Self {
+        Self {
+            error: error.to_string(),
+            stamp: file_stamp(path),
+        }
+    }
 }
 
 pub struct EntryCache {
     files: RwLock<HashMap<PathBuf, CachedFile>>,
+    /// Log files found but not readable
+    unreadable: RwLock<HashMap<PathBuf, UnreadableFile>>,
+    snapshot_path: Option<PathBuf>,
+    dirty: AtomicBool,
 }
 
 impl Default for EntryCache {
@@ -23,70 +120,572 @@ impl Default for EntryCache {
     }
 }
 
+#[cfg(unix)]
+pub(crate) fn file_id(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn file_id(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+/// Whether the byte before `offset` is still the newline we stopped at.
+/// Catches files rewritten in place to an equal or larger size.
+fn ends_line_at(path: &Path, offset: u64) -> bool {
+    if offset == 0 {
+        return true;
+    }
+
+    let mut byte = [0u8; 1];
+    File::open(path)
+        .and_then(|mut f| {
+            f.seek(SeekFrom::Start(offset - 1))?;
+            f.read_exact(&mut byte)
+        })
+        .map(|_| byte[0] == b'\n')
+        .unwrap_or(false)
+}
+
+/// Read a file into the cache, only parsing bytes appended since `previous`
+/// was read unless the file was truncated or replaced. Also returns the
+/// index of the first entry that wasn't in `previous`.
+fn load_file(path: &Path, previous: Option<CachedFile>) -> io::Result<(CachedFile, usize)> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified()?;
+    let size = metadata.len();
+    let id = file_id(&metadata);
+
+    let previous = previous.filter(|prev| {
+        let replaced = prev.file_id.is_some() && prev.file_id != id;
+        let rewritten = size == prev.size && modified != prev.modified_time;
+        !replaced && !rewritten && size >= prev.size && ends_line_at(path, prev.offset)
+    });
+
+    let mut cached = previous.unwrap_or_else(|| CachedFile {
+        path: path.to_path_buf(),
+        modified_time: modified,
+        entries: Vec::new(),
+        size: 0,
+        offset: 0,
+        file_id: id,
+        stats: ParseStats::default(),
+    });
+
+    let start = cached.entries.len();
+    if size > cached.offset {
+        let chunk = parse_jsonl_from(path, cached.offset)?;
+        cached.entries.extend(chunk.entries);
+        cached.offset = chunk.end_offset;
+        cached.stats.extend(chunk.stats);
+    }
+
+    cached.modified_time = modified;
+    cached.size = size;
+    Ok((cached, start))
+}
+
 impl EntryCache {
     pub fn new() -> Self {
         Self {
             files: RwLock::new(HashMap::new()),
+            unreadable: RwLock::new(HashMap::new()),
+            snapshot_path: None,
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// Create a cache that is seeded from, and saved to, an on-disk snapshot
+    pub fn with_snapshot(snapshot_path: PathBuf) -> Self {
+        Self {
+            snapshot_path: Some(snapshot_path),
+            ..Self::new()
         }
     }
 
-    /// Initialize cache by scanning and parsing all JSONL files
-    pub fn initialize(&self) {
-        let paths = get_claude_paths();
-        let files = find_jsonl_files(&paths);
+    /// Initialize cache by scanning all JSONL files under `roots`, re-parsing
+    /// only those that changed since the snapshot was written. Calling it
+    /// again later rescans against the loaded files.
+    pub fn initialize(&self, roots: &[PathBuf]) -> CacheDelta {
+        let files = find_jsonl_files(roots);
 
+        // A rescan while running starts from what is already loaded
         let mut cache = self.files.write().unwrap();
-        cache.clear();
+        let mut previous = if cache.is_empty() {
+            self.load_snapshot()
+        } else {
+            std::mem::take(&mut *cache)
+        };
+
+        let mut unreadable = self.unreadable.write().unwrap();
+        unreadable.clear();
 
+        let mut delta = CacheDelta::default();
+        let mut changed = false;
         for file_path in files {
-            if let Ok(metadata) = fs::metadata(&file_path) {
-                if let Ok(modified) = metadata.modified() {
-                    let entries = parse_jsonl_file(&file_path);
-                    cache.insert(
-                        file_path.clone(),
-                        CachedFile {
-                            path: file_path,
-                            modified_time: modified,
-                            entries,
-                        },
-                    );
+            let prev = previous.remove(&file_path);
+            let prev_state = prev.as_ref().map(|p| (p.modified_time, p.size));
+            let prev_len = prev.as_ref().map_or(0, |p| p.entries.len());
+
+            match load_file(&file_path, prev) {
+                Ok((cached, start)) => {
+                    changed |= prev_state != Some((cached.modified_time, cached.size));
+                    delta.record_load(&cached, start, prev_len);
+                    cache.insert(file_path, cached);
+                }
+                Err(e) => {
+                    changed |= prev_state.is_some();
+                    delta.reset |= prev_len > 0;
+                    let file = UnreadableFile::new(&file_path, e);
+                    unreadable.insert(file_path, file);
                 }
             }
         }
+        drop(unreadable);
+        // Anything left over was deleted since the last run
+        changed |= !previous.is_empty();
+        delta.reset |= previous.values().any(|p| !p.entries.is_empty());
+        drop(cache);
+
+        if changed {
+            self.dirty.store(true, Ordering::Relaxed);
+            self.save_snapshot();
+        }
+        delta
     }
 
-    /// Get all cached entries from all files
+    fn load_snapshot(&self) -> HashMap<PathBuf, CachedFile> {
+        let Some(path) = &self.snapshot_path else {
+            return HashMap::new();
+        };
+        let Ok(file) = File::open(path) else {
+            return HashMap::new();
+        };
+
+        match serde_json::from_reader::<_, Snapshot>(BufReader::new(file)) {
+            Ok(snapshot) if snapshot.version == SNAPSHOT_VERSION => snapshot
+                .files
+                .into_iter()
+                .map(|cf| (cf.path.clone(), cf))
+                .collect(),
+            Ok(snapshot) => {
+                eprintln!(
+                    "Discarding cache snapshot with version {} (expected {})",
+                    snapshot.version, SNAPSHOT_VERSION
+                );
+                HashMap::new()
+            }
+            Err(e) => {
+                eprintln!("Discarding unreadable cache snapshot: {:?}", e);
+                HashMap::new()
+            }
+        }
+    }
+
+    /// Write the cache to the snapshot file if it changed since the last save
+    pub fn save_snapshot(&self) {
+        let Some(path) = &self.snapshot_path else {
+            return;
+        };
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+
+        if let Err(e) = self.write_snapshot(path) {
+            eprintln!("Failed to write cache snapshot {:?}: {:?}", path, e);
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn write_snapshot(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write to a temporary file first so a crash never leaves a torn snapshot
+        let tmp_path = path.with_extension("tmp");
+        {
+            let cache = self.files.read().unwrap();
+            let snapshot = Snapshot {
+                version: SNAPSHOT_VERSION,
+                files: cache.values().cloned().collect(),
+            };
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            serde_json::to_writer(&mut writer, &snapshot)?;
+            writer.flush()?;
+        }
+        fs::rename(&tmp_path, path)
+    }
+
+    /// Get all cached entries from all files, deduplicated across files and
+    /// sorted by timestamp
     pub fn get_all_entries(&self) -> Vec<LogEntry> {
         let cache = self.files.read().unwrap();
-        cache
+        let entries = cache
             .values()
             .flat_map(|cf| cf.entries.clone())
+            .collect();
+        dedupe_entries(entries)
+    }
+
+    /// Whether `path` was loaded, or already found to be unreadable
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.read().unwrap().contains_key(path)
+            || self.unreadable.read().unwrap().contains_key(path)
+    }
+
+    /// Cached files whose size, modified time or identity no longer match
+    /// the disk, including ones that were deleted, and unreadable files
+    /// whose metadata changed since they were tried
+    pub fn stale_paths(&self) -> Vec<PathBuf> {
+        let cache = self.files.read().unwrap();
+        let unreadable = self.unreadable.read().unwrap();
+        cache
+            .values()
+            .filter(|cf| match fs::metadata(&cf.path) {
+                Ok(metadata) => {
+                    metadata.len() != cf.size
+                        || metadata.modified().ok() != Some(cf.modified_time)
+                        || file_id(&metadata) != cf.file_id
+                }
+                Err(_) => true,
+            })
+            .map(|cf| cf.path.clone())
+            .chain(
+                unreadable
+                    .iter()
+                    .filter(|(path, file)| file_stamp(path) != file.stamp)
+                    .map(|(path, _)| path.clone()),
+            )
             .collect()
     }
 
     /// Invalidate and refresh specific files (called by watcher)
-    pub fn invalidate_paths(&self, changed_paths: &[PathBuf]) {
+    pub fn invalidate_paths(&self, changed_paths: &[PathBuf]) -> CacheDelta {
         let mut cache = self.files.write().unwrap();
+        self.dirty.store(true, Ordering::Relaxed);
 
+        let mut unreadable = self.unreadable.write().unwrap();
+
+        let mut delta = CacheDelta::default();
         for path in changed_paths {
+            let previous = cache.remove(path);
+            let prev_len = previous.as_ref().map_or(0, |p| p.entries.len());
+            unreadable.remove(path);
+
             if path.exists() {
-                if let Ok(metadata) = fs::metadata(path) {
-                    if let Ok(modified) = metadata.modified() {
-                        let entries = parse_jsonl_file(path);
-                        cache.insert(
-                            path.clone(),
-                            CachedFile {
-                                path: path.clone(),
-                                modified_time: modified,
-                                entries,
-                            },
-                        );
+                match load_file(path, previous) {
+                    Ok((cached, start)) => {
+                        delta.record_load(&cached, start, prev_len);
+                        cache.insert(path.clone(), cached);
+                        continue;
+                    }
+                    Err(e) => {
+                        unreadable.insert(path.clone(), UnreadableFile::new(path, e));
                     }
                 }
-            } else {
-                // File was deleted
-                cache.remove(path);
             }
+            // File was deleted or can no longer be read
+            delta.reset |= prev_len > 0;
         }
+
+        delta
+    }
+
+    /// Parse stats for every log file, plus the ones that couldn't be read,
+    /// with the most malformed lines first
+    pub fn diagnostics(&self) -> Vec<FileDiagnostics> {
+        let cache = self.files.read().unwrap();
+        let unreadable = self.unreadable.read().unwrap();
+
+        let mut diagnostics: Vec<FileDiagnostics> = cache
+            .values()
+            .map(|cf| FileDiagnostics {
+                path: cf.path.to_string_lossy().to_string(),
+                error: None,
+                stats: cf.stats.clone(),
+            })
+            .chain(unreadable.iter().map(|(path, file)| FileDiagnostics {
+                path: path.to_string_lossy().to_string(),
+                error: Some(file.error.clone()),
+                stats: ParseStats::default(),
+            }))
+            .collect();
+
+        diagnostics.sort_by(|a, b| {
+            b.error
+                .is_some()
+                .cmp(&a.error.is_some())
+                .then(b.stats.malformed_lines().cmp(&a.stats.malformed_lines()))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn line(message_id: &str) -> String {
+        format!(
+            "{{\"timestamp\":\"2025-06-01T10:00:00Z\",\"requestId\":\"r-{0}\",\"message\":{{\"id\":\"{0}\",\"model\":\"claude-sonnet-4\",\"usage\":{{\"input_tokens\":1,\"output_tokens\":2}}}}}}\n",
+            message_id
+        )
+    }
+
+    fn ids(cached: &CachedFile) -> Vec<&str> {
+        cached
+            .entries
+            .iter()
+            .map(|e| e.message.id.as_deref().unwrap())
+            .collect()
+    }
+
+    fn append(path: &Path, text: &str) {
+        let mut file = fs::OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn appended_lines_are_parsed_from_the_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.jsonl");
+        fs::write(&path, line("a")).unwrap();
+        let (first, _) = load_file(&path, None).unwrap();
+
+        append(&path, &line("b"));
+        let (cached, start) = load_file(&path, Some(first)).unwrap();
+        assert_eq!(ids(&cached), ["a", "b"]);
+        assert_eq!(start, 1);
+        assert_eq!(cached.offset, fs::metadata(&path).unwrap().len());
+    }
+
+    #[test]
+    fn partial_last_line_waits_for_its_newline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.jsonl");
+        let b = line("b");
+        let (head, tail) = b.split_at(20);
+        fs::write(&path, line("a") + head).unwrap();
+
+        let (cached, _) = load_file(&path, None).unwrap();
+        assert_eq!(ids(&cached), ["a"]);
+        assert_eq!(cached.offset, line("a").len() as u64);
+
+        append(&path, tail);
+        let (cached, start) = load_file(&path, Some(cached)).unwrap();
+        assert_eq!(ids(&cached), ["a", "b"]);
+        assert_eq!(start, 1);
+    }
+
+    #[test]
+    fn shrunk_file_is_parsed_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.jsonl");
+        fs::write(&path, line("a") + &line("b")).unwrap();
+        let (first, _) = load_file(&path, None).unwrap();
+
+        fs::write(&path, line("c")).unwrap();
+        let (cached, start) = load_file(&path, Some(first)).unwrap();
+        assert_eq!(ids(&cached), ["c"]);
+        assert_eq!(start, 0);
+    }
+
+    #[test]
+    fn rewrite_to_the_same_size_is_parsed_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.jsonl");
+        fs::write(&path, line("a") + &line("b")).unwrap();
+        let (first, _) = load_file(&path, None).unwrap();
+
+        fs::write(&path, line("c") + &line("d")).unwrap();
+        // Coarse filesystem clocks could give the rewrite the same mtime
+        let file = File::options().write(true).open(&path).unwrap();
+        file.set_modified(first.modified_time + Duration::from_secs(5))
+            .unwrap();
+
+        let (cached, start) = load_file(&path, Some(first)).unwrap();
+        assert_eq!(ids(&cached), ["c", "d"]);
+        assert_eq!(start, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn replaced_file_is_parsed_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.jsonl");
+        fs::write(&path, line("a")).unwrap();
+        let (first, _) = load_file(&path, None).unwrap();
+
+        // Same line layout, so only the new inode gives the swap away
+        let replacement = dir.path().join("replacement.jsonl");
+        fs::write(&replacement, line("c") + &line("d")).unwrap();
+        fs::rename(&replacement, &path).unwrap();
+
+        let (cached, start) = load_file(&path, Some(first)).unwrap();
+        assert_eq!(ids(&cached), ["c", "d"]);
+        assert_eq!(start, 0);
+    }
+
+    /// Lines read and malformed lines counted for a file
+    fn counts(cached: &CachedFile) -> (u64, u64, usize) {
+        let stats = &cached.stats;
+        (
+            stats.lines_read,
+            stats.malformed_lines(),
+            stats.samples.len(),
+        )
+    }
+
+    #[test]
+    fn stats_start_over_when_the_file_is_read_from_the_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.jsonl");
+        fs::write(&path, line("a") + "{broken\n").unwrap();
+        let (first, _) = load_file(&path, None).unwrap();
+        assert_eq!(counts(&first), (2, 1, 1));
+
+        append(&path, &line("b"));
+        let (appended, _) = load_file(&path, Some(first)).unwrap();
+        assert_eq!(counts(&appended), (3, 1, 1));
+
+        fs::write(&path, line("c")).unwrap();
+        let (truncated, _) = load_file(&path, Some(appended)).unwrap();
+        assert_eq!(counts(&truncated), (1, 0, 0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stats_start_over_when_the_file_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.jsonl");
+        fs::write(&path, "{broken\n".to_string() + &line("a")).unwrap();
+        let (first, _) = load_file(&path, None).unwrap();
+        assert_eq!(counts(&first), (2, 1, 1));
+
+        // Longer than the original, so only the new inode gives it away
+        let replacement = dir.path().join("replacement.jsonl");
+        fs::write(&replacement, line("c") + &line("d") + &line("e")).unwrap();
+        fs::rename(&replacement, &path).unwrap();
+        let (cached, _) = load_file(&path, Some(first)).unwrap();
+        assert_eq!(counts(&cached), (3, 0, 0));
+    }
+
+    #[test]
+    fn diagnostics_list_unreadable_files_then_the_most_malformed() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, malformed: usize| {
+            let path = dir.path().join(name);
+            fs::write(&path, line(name) + &"{broken\n".repeat(malformed)).unwrap();
+            path
+        };
+        // A directory can be stat'ed but not read as a log
+        let unreadable = |name: &str| {
+            let path = dir.path().join(name);
+            fs::create_dir(&path).unwrap();
+            fs::write(path.join("inside"), "").unwrap();
+            path
+        };
+        let paths = [
+            write("a-some.jsonl", 1),
+            write("b-clean.jsonl", 0),
+            unreadable("n-unreadable.jsonl"),
+            write("z-most.jsonl", 2),
+            write("c-clean.jsonl", 0),
+            unreadable("m-unreadable.jsonl"),
+        ];
+        let cache = EntryCache::new();
+        cache.invalidate_paths(&paths);
+
+        let order: Vec<_> = cache
+            .diagnostics()
+            .into_iter()
+            .map(|d| {
+                let name = Path::new(&d.path).file_name().unwrap();
+                (name.to_string_lossy().to_string(), d.error.is_some())
+            })
+            .collect();
+        let expected = [
+            ("m-unreadable.jsonl", true),
+            ("n-unreadable.jsonl", true),
+            ("z-most.jsonl", false),
+            ("a-some.jsonl", false),
+            ("b-clean.jsonl", false),
+            ("c-clean.jsonl", false),
+        ];
+        assert_eq!(
+            order,
+            expected.map(|(name, error)| (name.to_string(), error))
+        );
+    }
+
+    /// A cache that has read one log file under a fresh root and saved its
+    /// snapshot
+    fn saved_cache(dir: &Path) -> (PathBuf, PathBuf) {
+        let root = dir.join("projects");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("log.jsonl"), line("a")).unwrap();
+
+        let snapshot = dir.join("snapshot.json");
+        EntryCache::with_snapshot(snapshot.clone()).initialize(std::slice::from_ref(&root));
+        assert!(snapshot.exists());
+        (root, snapshot)
+    }
+
+    fn edit_snapshot(path: &Path, edit: impl FnOnce(&mut serde_json::Value)) {
+        let mut value: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        edit(&mut value);
+        fs::write(path, value.to_string()).unwrap();
+    }
+
+    #[test]
+    fn mismatched_or_corrupt_snapshots_are_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, snapshot) = saved_cache(dir.path());
+        let cache = EntryCache::with_snapshot(snapshot.clone());
+        assert_eq!(cache.load_snapshot().len(), 1);
+
+        edit_snapshot(&snapshot, |value| {
+            value["version"] = (SNAPSHOT_VERSION - 1).into();
+        });
+        assert!(cache.load_snapshot().is_empty());
+
+        fs::write(&snapshot, "{\"version\":").unwrap();
+        assert!(cache.load_snapshot().is_empty());
+    }
+
+    #[test]
+    fn unchanged_files_are_not_parsed_again_after_a_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let (root, snapshot) = saved_cache(dir.path());
+        // Only a cache that trusts the snapshot can see this id
+        edit_snapshot(&snapshot, |value| {
+            value["files"][0]["entries"][0]["message"]["id"] = "from-snapshot".into();
+        });
+
+        let cache = EntryCache::with_snapshot(snapshot);
+        let delta = cache.initialize(&[root]);
+        assert_eq!(delta.new_entries, 0);
+        let entries = cache.get_all_entries();
+        assert_eq!(entries[0].message.id.as_deref(), Some("from-snapshot"));
+    }
+
+    #[test]
+    fn invalidated_files_are_saved_with_the_next_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let (root, snapshot) = saved_cache(dir.path());
+        let log = root.join("log.jsonl");
+        let cache = EntryCache::with_snapshot(snapshot.clone());
+        cache.initialize(&[root]);
+
+        append(&log, &line("b"));
+        cache.invalidate_paths(&[log]);
+        cache.save_snapshot();
+        let reloaded = EntryCache::with_snapshot(snapshot).load_snapshot();
+        assert_eq!(reloaded.values().next().unwrap().entries.len(), 2);
     }
 }
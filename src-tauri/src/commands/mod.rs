@@ -1,59 +1,201 @@
-use chrono::{DateTime, Datelike, Duration, Local, NaiveDate};
-use tauri::State;
+use tauri::{AppHandle, State};
 
 use crate::cache::EntryCache;
 use crate::parser::{
-    aggregate_usage, calculate_billing_windows, get_session_breakdown,
-    types::{BillingWindow, LogEntry, SessionSummary, UsageData},
+    aggregate_usage,
+    blocks::billing_windows_for_range,
+    build_usage_series, filter_entries_by_time_range, get_project_breakdown, get_session_breakdown,
+    range::{ReportTimeZone, TimeRange},
+    types::{
+        BillingWindow, ParseDiagnostics, ProjectSummary, SeriesGranularity, SessionSummary,
+        UsageData, UsageSeries,
+    },
 };
+use crate::settings::{Settings, SettingsStore};
+use crate::tray::{refresh_now, refresh_tray};
 
-/// Filter entries by time range
-fn filter_entries_by_time_range(entries: &[LogEntry], time_range: &str) -> Vec<LogEntry> {
-    let now = Local::now();
-    let filter_date = match time_range {
-        "today" => now.date_naive(),
-        "week" => (now - Duration::days(7)).date_naive(),
-        "month" => {
-            NaiveDate::from_ymd_opt(now.year(), now.month(), 1).unwrap_or(now.date_naive())
-        }
-        _ => NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
-    };
+/// Resolve a named range, or "today" etc. in an explicit zone when given
+fn preset_range(time_range: &str, timezone: Option<&str>) -> Result<TimeRange, String> {
+    let tz = ReportTimeZone::parse(timezone).map_err(|e| e.to_string())?;
+    Ok(TimeRange::preset(time_range, tz))
+}
 
-    entries
-        .iter()
-        .filter(|entry| {
-            if let Ok(dt) = DateTime::parse_from_rfc3339(&entry.timestamp) {
-                let entry_date = dt.with_timezone(&Local).date_naive();
-                if time_range == "today" {
-                    entry_date == filter_date
-                } else {
-                    entry_date >= filter_date
-                }
-            } else {
-                false
-            }
-        })
-        .cloned()
-        .collect()
+fn explicit_range(start: &str, end: &str, timezone: Option<&str>) -> Result<TimeRange, String> {
+    let tz = ReportTimeZone::parse(timezone).map_err(|e| e.to_string())?;
+    TimeRange::parse(start, end, tz).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn get_usage_data(time_range: String, cache: State<'_, EntryCache>) -> Result<UsageData, String> {
+pub fn get_usage_data(
+    time_range: String,
+    timezone: Option<String>,
+    cache: State<'_, EntryCache>,
+) -> Result<UsageData, String> {
+    let range = preset_range(&time_range, timezone.as_deref())?;
     let all_entries = cache.get_all_entries();
-    let filtered = filter_entries_by_time_range(&all_entries, &time_range);
-    Ok(aggregate_usage(&filtered))
+    Ok(aggregate_usage(&range.filter(&all_entries)))
 }
 
+/// Usage between two bounds, each RFC 3339 or a date (an end date is
+/// inclusive), with calendar dates read in `timezone` (IANA, default local)
 #[tauri::command]
-pub fn get_billing_windows(cache: State<'_, EntryCache>) -> Result<Vec<BillingWindow>, String> {
+pub fn get_usage_data_for_range(
+    start: String,
+    end: String,
+    timezone: Option<String>,
+    cache: State<'_, EntryCache>,
+) -> Result<UsageData, String> {
+    let range = explicit_range(&start, &end, timezone.as_deref())?;
     let all_entries = cache.get_all_entries();
-    let filtered = filter_entries_by_time_range(&all_entries, "today");
-    Ok(calculate_billing_windows(&filtered))
+    Ok(aggregate_usage(&range.filter(&all_entries)))
+}
+
+/// Billing windows overlapping a named range, "today" by default. A block
+/// that started before the range is included whole.
+#[tauri::command]
+pub fn get_billing_windows(
+    time_range: Option<String>,
+    timezone: Option<String>,
+    cache: State<'_, EntryCache>,
+    settings: State<'_, SettingsStore>,
+) -> Result<Vec<BillingWindow>, String> {
+    let range = preset_range(
+        time_range.as_deref().unwrap_or("today"),
+        timezone.as_deref(),
+    )?;
+    let plan = settings.get().plan;
+    let all_entries = cache.get_all_entries();
+    Ok(billing_windows_for_range(
+        &all_entries,
+        &range,
+        plan.as_ref(),
+    ))
+}
+
+/// Billing windows between two bounds, see `get_usage_data_for_range`
+#[tauri::command]
+pub fn get_billing_windows_for_range(
+    start: String,
+    end: String,
+    timezone: Option<String>,
+    cache: State<'_, EntryCache>,
+    settings: State<'_, SettingsStore>,
+) -> Result<Vec<BillingWindow>, String> {
+    let range = explicit_range(&start, &end, timezone.as_deref())?;
+    let plan = settings.get().plan;
+    let all_entries = cache.get_all_entries();
+    Ok(billing_windows_for_range(
+        &all_entries,
+        &range,
+        plan.as_ref(),
+    ))
 }
 
 #[tauri::command]
-pub fn get_session_breakdown_cmd(cache: State<'_, EntryCache>) -> Result<Vec<SessionSummary>, String> {
+pub fn get_session_breakdown_cmd(
+    cache: State<'_, EntryCache>,
+) -> Result<Vec<SessionSummary>, String> {
     let all_entries = cache.get_all_entries();
     let filtered = filter_entries_by_time_range(&all_entries, "today");
     Ok(get_session_breakdown(&filtered))
 }
+
+/// Sessions between two bounds, see `get_usage_data_for_range`
+#[tauri::command]
+pub fn get_session_breakdown_for_range(
+    start: String,
+    end: String,
+    timezone: Option<String>,
+    cache: State<'_, EntryCache>,
+) -> Result<Vec<SessionSummary>, String> {
+    let range = explicit_range(&start, &end, timezone.as_deref())?;
+    let all_entries = cache.get_all_entries();
+    Ok(get_session_breakdown(&range.filter(&all_entries)))
+}
+
+#[tauri::command]
+pub fn get_project_breakdown_cmd(
+    time_range: String,
+    timezone: Option<String>,
+    cache: State<'_, EntryCache>,
+) -> Result<Vec<ProjectSummary>, String> {
+    let range = preset_range(&time_range, timezone.as_deref())?;
+    let all_entries = cache.get_all_entries();
+    Ok(get_project_breakdown(&range.filter(&all_entries)))
+}
+
+/// Projects between two bounds, see `get_usage_data_for_range`
+#[tauri::command]
+pub fn get_project_breakdown_for_range(
+    start: String,
+    end: String,
+    timezone: Option<String>,
+    cache: State<'_, EntryCache>,
+) -> Result<Vec<ProjectSummary>, String> {
+    let range = explicit_range(&start, &end, timezone.as_deref())?;
+    let all_entries = cache.get_all_entries();
+    Ok(get_project_breakdown(&range.filter(&all_entries)))
+}
+
+/// Per-day (or per-hour, for ranges up to two days) cost and token buckets
+/// for charting. `granularity` may force "day" or "hour".
+#[tauri::command]
+pub fn get_daily_series(
+    start: String,
+    end: String,
+    timezone: Option<String>,
+    granularity: Option<String>,
+    cache: State<'_, EntryCache>,
+) -> Result<UsageSeries, String> {
+    let range = explicit_range(&start, &end, timezone.as_deref())?;
+    let granularity = match granularity.as_deref() {
+        None => None,
+        Some("day") => Some(SeriesGranularity::Day),
+        Some("hour") => Some(SeriesGranularity::Hour),
+        Some(other) => return Err(format!("Unknown granularity: {}", other)),
+    };
+
+    let all_entries = cache.get_all_entries();
+    build_usage_series(&all_entries, &range, granularity).map_err(|e| e.to_string())
+}
+
+/// Log directories that weren't found, and the lines read, kept and skipped
+/// per log file, worst files first
+#[tauri::command]
+pub fn get_parse_diagnostics(
+    cache: State<'_, EntryCache>,
+    settings: State<'_, SettingsStore>,
+) -> ParseDiagnostics {
+    let roots = settings.get().log_roots();
+    ParseDiagnostics {
+        missing_roots: roots
+            .missing
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect(),
+        files: cache.diagnostics(),
+    }
+}
+
+#[tauri::command]
+pub fn get_settings(settings: State<'_, SettingsStore>) -> Settings {
+    settings.get()
+}
+
+#[tauri::command]
+pub fn update_settings(
+    new_settings: Settings,
+    settings: State<'_, SettingsStore>,
+    app: AppHandle,
+) -> Result<Settings, String> {
+    let roots_changed = new_settings.extra_log_dirs != settings.get().extra_log_dirs;
+    settings.update(new_settings).map_err(|e| e.to_string())?;
+
+    if roots_changed {
+        settings.get().log_roots().report_missing();
+        refresh_now(&app);
+    } else {
+        refresh_tray(&app);
+    }
+    Ok(settings.get())
+}
@@ -1,93 +1,101 @@
-mod cache;
+pub mod alerts;
+pub mod cache;
+pub mod parser;
+pub mod pricing;
+pub mod settings;
+pub mod watcher;
+
+// Everything below needs Tauri, and with it the webview system libraries
+#[cfg(feature = "app")]
 mod commands;
-mod parser;
-mod pricing;
-mod watcher;
+#[cfg(feature = "app")]
+mod tray;
 
+#[cfg(feature = "app")]
+use alerts::AlertState;
+#[cfg(feature = "app")]
 use cache::EntryCache;
+#[cfg(feature = "app")]
+use settings::SettingsStore;
 
-use tauri::{
-    image::Image,
-    menu::{Menu, MenuItem},
-    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    ActivationPolicy, Manager,
-};
-use tauri_plugin_positioner::{Position, WindowExt};
+#[cfg(feature = "app")]
+use tauri::{ActivationPolicy, Manager};
 
-fn load_icon() -> Image<'static> {
-    let png_data = include_bytes!("../icons/icon.png");
-    let img = image::load_from_memory(png_data).expect("failed to load icon");
-    let rgba = img.to_rgba8();
-    let (width, height) = rgba.dimensions();
-    Image::new_owned(rgba.into_raw(), width, height)
-}
+#[cfg(feature = "app")]
+const CACHE_SNAPSHOT_FILE: &str = "entry-cache.json";
+#[cfg(feature = "app")]
+const ALERT_STATE_FILE: &str = "fired-alerts.json";
 
+#[cfg(feature = "app")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_positioner::init())
-        .manage(EntryCache::new())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .setup(|app| {
             #[cfg(target_os = "macos")]
             app.set_activation_policy(ActivationPolicy::Accessory);
 
-            // Initialize cache before starting watcher
-            let cache = app.state::<EntryCache>();
-            cache.initialize();
-
-            // Start file watcher for real-time updates
-            watcher::start_file_watcher(app.handle().clone());
+            if let Err(e) = pricing::reload_user_pricing() {
+                eprintln!("Ignoring pricing file: {}", e);
+            }
 
-            let quit_i = MenuItem::with_id(app, "quit", "Quit Claude Usage", true, None::<&str>)?;
+            let settings = SettingsStore::load();
+            let roots = settings.get().log_roots();
+            roots.report_missing();
 
-            let menu = Menu::with_items(app, &[&quit_i])?;
+            // Initialize cache before starting watcher, reusing the snapshot
+            // from the last run so only changed files are re-parsed
+            let (cache, alert_state) = match app.path().app_data_dir() {
+                Ok(dir) => (
+                    EntryCache::with_snapshot(dir.join(CACHE_SNAPSHOT_FILE)),
+                    AlertState::with_file(dir.join(ALERT_STATE_FILE)),
+                ),
+                Err(e) => {
+                    eprintln!("No app data directory, cache will not persist: {:?}", e);
+                    (EntryCache::new(), AlertState::new())
+                }
+            };
+            cache.initialize(&roots.found);
+            app.manage(cache);
+            app.manage(alert_state);
+            app.manage(settings);
 
-            let _tray = TrayIconBuilder::new()
-                .icon(load_icon())
-                .icon_as_template(true)
-                .menu(&menu)
-                .show_menu_on_left_click(false)
-                .on_menu_event(|app, event| match event.id.as_ref() {
-                    "quit" => {
-                        app.exit(0);
-                    }
-                    _ => {}
-                })
-                .on_tray_icon_event(|tray, event| {
-                    tauri_plugin_positioner::on_tray_event(tray.app_handle(), &event);
+            // Start file watcher for real-time updates
+            watcher::start_file_watcher(app.handle().clone());
 
-                    if let TrayIconEvent::Click {
-                        button: MouseButton::Left,
-                        button_state: MouseButtonState::Up,
-                        ..
-                    } = event
-                    {
-                        let app = tray.app_handle();
-                        if let Some(window) = app.get_webview_window("dashboard") {
-                            if window.is_visible().unwrap_or(false) {
-                                let _ = window.hide();
-                            } else {
-                                let _ = window.move_window(Position::TrayBottomCenter);
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                            }
-                        }
-                    }
-                })
-                .build(app)?;
+            tray::build_tray(app)?;
+            tray::refresh_tray(app.handle());
+            tray::start_refresh_timer(app.handle().clone());
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::get_usage_data,
+            commands::get_usage_data_for_range,
             commands::get_billing_windows,
+            commands::get_billing_windows_for_range,
             commands::get_session_breakdown_cmd,
+            commands::get_session_breakdown_for_range,
+            commands::get_project_breakdown_cmd,
+            commands::get_project_breakdown_for_range,
+            commands::get_daily_series,
+            commands::get_parse_diagnostics,
+            commands::get_settings,
+            commands::update_settings,
         ])
         .on_window_event(|window, event| {
             if let tauri::WindowEvent::Focused(false) = event {
                 let _ = window.hide();
             }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                app.state::<EntryCache>().save_snapshot();
+            }
+        });
 }
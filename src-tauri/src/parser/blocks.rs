@@ -0,0 +1,713 @@
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::range::{ReportTimeZone, TimeRange};
+use super::types::{BillingWindow, BlockProjection, BurnRate, LimitSource, LogEntry, UsageLimit};
+use crate::pricing::calculate_cost;
+
+/// Length of one usage block
+pub const BLOCK_DURATION_HOURS: i64 = 5;
+
+/// How far back the burn rate of the active block looks, so it follows the
+/// current pace rather than the average since the block started
+pub const BURN_RATE_WINDOW_MINUTES: i64 = 30;
+
+/// Approximate per-block token caps, counting input, output and cache
+/// tokens together. Anthropic doesn't publish exact figures.
+pub const PRO_TOKEN_LIMIT: u64 = 45_000_000;
+pub const MAX5_TOKEN_LIMIT: u64 = 225_000_000;
+pub const MAX20_TOKEN_LIMIT: u64 = 900_000_000;
+
+/// Subscription plan, which sets the cap each block is measured against
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Plan {
+    Pro,
+    Max5,
+    Max20,
+    Custom {
+        #[serde(rename = "tokenLimit", default)]
+        token_limit: Option<u64>,
+        #[serde(rename = "costLimitUsd", default)]
+        cost_limit_usd: Option<f64>,
+    },
+}
+
+impl Plan {
+    /// The named plans: "pro", "max5" or "max20"
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "pro" => Some(Plan::Pro),
+            "max5" => Some(Plan::Max5),
+            "max20" => Some(Plan::Max20),
+            _ => None,
+        }
+    }
+
+    /// None for a custom plan without any cap
+    pub fn limit(&self) -> Option<UsageLimit> {
+        let (token_limit, cost_limit_usd) = match *self {
+            Plan::Pro => (Some(PRO_TOKEN_LIMIT), None),
+            Plan::Max5 => (Some(MAX5_TOKEN_LIMIT), None),
+            Plan::Max20 => (Some(MAX20_TOKEN_LIMIT), None),
+            Plan::Custom {
+                token_limit,
+                cost_limit_usd,
+            } => (token_limit, cost_limit_usd),
+        };
+        if token_limit.is_none() && cost_limit_usd.is_none() {
+            return None;
+        }
+
+        Some(UsageLimit {
+            token_limit,
+            cost_limit_usd,
+            source: LimitSource::Plan,
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Plan::Custom {
+            token_limit,
+            cost_limit_usd,
+        } = *self
+        {
+            if token_limit.is_none() && cost_limit_usd.is_none() {
+                return Err("a custom plan needs a token or cost limit".to_string());
+            }
+            if token_limit == Some(0) {
+                return Err("token limit must be positive".to_string());
+            }
+            if cost_limit_usd.is_some_and(|c| !c.is_finite() || c <= 0.0) {
+                return Err("cost limit must be a positive number".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Block<'a> {
+    start: DateTime<Utc>,
+    entries: Vec<(DateTime<Utc>, &'a LogEntry)>,
+}
+
+impl Block<'_> {
+    fn end(&self) -> DateTime<Utc> {
+        self.start + Duration::hours(BLOCK_DURATION_HOURS)
+    }
+
+    fn total_tokens(&self) -> u64 {
+        self.entries
+            .iter()
+            .filter_map(|(_, e)| e.message.usage.as_ref())
+            .map(|usage| usage.total_tokens())
+            .sum()
+    }
+}
+
+pub fn calculate_billing_windows(entries: &[LogEntry]) -> Vec<BillingWindow> {
+    calculate_billing_windows_at(entries, Utc::now())
+}
+
+/// Every window in `entries`, see `billing_windows_for_range_at`
+pub fn calculate_billing_windows_at(
+    entries: &[LogEntry],
+    now: DateTime<Utc>,
+) -> Vec<BillingWindow> {
+    let all_time = TimeRange {
+        start: DateTime::<Utc>::MIN_UTC,
+        end: DateTime::<Utc>::MAX_UTC,
+        tz: ReportTimeZone::Local,
+    };
+    billing_windows_for_range_at(entries, &all_time, None, now)
+}
+
+pub fn billing_windows_for_range(
+    entries: &[LogEntry],
+    range: &TimeRange,
+    plan: Option<&Plan>,
+) -> Vec<BillingWindow> {
+    billing_windows_for_range_at(entries, range, plan, Utc::now())
+}
+
+/// Split entries into 5-hour blocks the way Claude counts them: a block
+/// starts at the hour containing the first message after the previous block
+/// ended and covers `[start, start + 5h)`. Idle stretches between blocks are
+/// returned as gap windows so the timeline has no holes.
+///
+/// A block ends once its five hours are up, so five hours of inactivity
+/// always closes it too; there is no separate idle timeout.
+///
+/// `entries` should be the whole history rather than just the range, since
+/// where a block starts depends on the blocks before it. Windows overlapping
+/// the range are returned whole, so one that began before midnight still
+/// shows up, with all its usage, in "today".
+///
+/// Blocks are measured against `plan`'s cap or, without a plan, against the
+/// most tokens any finished block has used.
+pub fn billing_windows_for_range_at(
+    entries: &[LogEntry],
+    range: &TimeRange,
+    plan: Option<&Plan>,
+    now: DateTime<Utc>,
+) -> Vec<BillingWindow> {
+    let mut timed: Vec<(DateTime<Utc>, &LogEntry)> = entries
+        .iter()
+        .filter_map(|e| e.parsed_timestamp().map(|dt| (dt, e)))
+        .collect();
+    timed.sort_by_key(|(dt, _)| *dt);
+
+    let mut blocks: Vec<Block> = Vec::new();
+    for (time, entry) in timed {
+        match blocks.last_mut() {
+            Some(block) if time < block.end() => block.entries.push((time, entry)),
+            _ => blocks.push(Block {
+                start: floor_to_hour(time),
+                entries: vec![(time, entry)],
+            }),
+        }
+    }
+
+    let limit = match plan {
+        Some(plan) => plan.limit(),
+        None => historical_limit(&blocks, now),
+    };
+
+    let overlaps =
+        |start: DateTime<Utc>, end: DateTime<Utc>| start < range.end && end > range.start;
+
+    let mut windows = Vec::new();
+    let mut previous_end: Option<DateTime<Utc>> = None;
+    for block in &blocks {
+        if let Some(gap_start) = previous_end.filter(|end| *end < block.start) {
+            if overlaps(gap_start, block.start) {
+                windows.push(gap_window(gap_start, block.start));
+            }
+        }
+        if overlaps(block.start, block.end()) {
+            windows.push(block_window(block, limit.as_ref(), now));
+        }
+        previous_end = Some(block.end());
+    }
+
+    windows
+}
+
+fn floor_to_hour(dt: DateTime<Utc>) -> DateTime<Utc> {
+    dt.duration_trunc(Duration::hours(1)).unwrap_or(dt)
+}
+
+/// Cap used when no plan is set: the busiest finished block so far
+fn historical_limit(blocks: &[Block], now: DateTime<Utc>) -> Option<UsageLimit> {
+    blocks
+        .iter()
+        .filter(|block| block.end() <= now)
+        .map(|block| block.total_tokens())
+        .max()
+        .filter(|tokens| *tokens > 0)
+        .map(|tokens| UsageLimit {
+            token_limit: Some(tokens),
+            cost_limit_usd: None,
+            source: LimitSource::Historical,
+        })
+}
+
+/// Share of the cap used, taking whichever of the token and cost caps is
+/// closer to being reached
+fn percent_of_limit(limit: &UsageLimit, tokens: u64, cost: f64) -> Option<f64> {
+    let by_tokens = limit
+        .token_limit
+        .filter(|cap| *cap > 0)
+        .map(|cap| tokens as f64 / cap as f64 * 100.0);
+    let by_cost = limit
+        .cost_limit_usd
+        .filter(|cap| *cap > 0.0)
+        .map(|cap| cost / cap * 100.0);
+
+    match (by_tokens, by_cost) {
+        (Some(t), Some(c)) => Some(t.max(c)),
+        (t, c) => t.or(c),
+    }
+}
+
+/// When the cap will be reached if the burn rate holds. None once it has
+/// been reached, or when the pace won't get there before the block ends.
+fn predict_limit_time(
+    limit: &UsageLimit,
+    tokens: u64,
+    cost: f64,
+    rate: &BurnRate,
+    now: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    if limit.token_limit.is_some_and(|cap| tokens >= cap)
+        || limit.cost_limit_usd.is_some_and(|cap| cost >= cap)
+    {
+        return None;
+    }
+
+    let token_minutes = limit
+        .token_limit
+        .filter(|_| rate.tokens_per_minute > 0.0)
+        .map(|cap| (cap - tokens) as f64 / rate.tokens_per_minute);
+    let cost_minutes = limit
+        .cost_limit_usd
+        .filter(|_| rate.cost_per_hour > 0.0)
+        .map(|cap| (cap - cost) / rate.cost_per_hour * 60.0);
+    let minutes = match (token_minutes, cost_minutes) {
+        (Some(t), Some(c)) => t.min(c),
+        (t, c) => t.or(c)?,
+    };
+
+    let remaining_minutes = (end_time - now).num_seconds() as f64 / 60.0;
+    if minutes >= remaining_minutes {
+        return None;
+    }
+    Some(now + Duration::seconds((minutes * 60.0).round() as i64))
+}
+
+fn block_window(block: &Block, limit: Option<&UsageLimit>, now: DateTime<Utc>) -> BillingWindow {
+    let end_time = block.end();
+    let is_active = now >= block.start && now < end_time;
+
+    let rate_start = block
+        .start
+        .max(now - Duration::minutes(BURN_RATE_WINDOW_MINUTES));
+
+    let mut total_tokens = 0u64;
+    let mut total_cost = 0.0;
+    let mut recent_tokens = 0u64;
+    let mut recent_cost = 0.0;
+    for (time, e) in &block.entries {
+        if let Some(ref usage) = e.message.usage {
+            let tokens = usage.total_tokens();
+            let model = e
+                .message
+                .model
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string());
+            let cost = e
+                .cost_usd
+                .unwrap_or_else(|| calculate_cost(&model, usage, &e.timestamp));
+
+            total_tokens += tokens;
+            total_cost += cost;
+            if *time >= rate_start && *time <= now {
+                recent_tokens += tokens;
+                recent_cost += cost;
+            }
+        }
+    }
+
+    // Rates only mean something while the block is still running
+    let (burn_rate, projection) = if is_active {
+        let elapsed_minutes = ((now - rate_start).num_seconds() as f64 / 60.0).max(1.0);
+        let remaining_minutes = (end_time - now).num_seconds() as f64 / 60.0;
+        let tokens_per_minute = recent_tokens as f64 / elapsed_minutes;
+        let cost_per_minute = recent_cost / elapsed_minutes;
+
+        (
+            Some(BurnRate {
+                tokens_per_minute,
+                cost_per_hour: cost_per_minute * 60.0,
+            }),
+            Some(BlockProjection {
+                total_tokens: total_tokens + (tokens_per_minute * remaining_minutes).round() as u64,
+                cost_usd: total_cost + cost_per_minute * remaining_minutes,
+            }),
+        )
+    } else {
+        (None, None)
+    };
+
+    let percent_used = limit.and_then(|l| percent_of_limit(l, total_tokens, total_cost));
+    let predicted_limit_time = match (limit, &burn_rate) {
+        (Some(l), Some(rate)) => {
+            predict_limit_time(l, total_tokens, total_cost, rate, now, end_time)
+        }
+        _ => None,
+    };
+
+    BillingWindow {
+        id: block.start.to_rfc3339(),
+        start_time: block.start.to_rfc3339(),
+        end_time: end_time.to_rfc3339(),
+        total_tokens,
+        cost_usd: total_cost,
+        remaining_minutes: (end_time - now).num_minutes().max(0),
+        is_active,
+        is_gap: false,
+        last_entry_time: block.entries.last().map(|(dt, _)| dt.to_rfc3339()),
+        burn_rate,
+        projection,
+        limit: limit.cloned(),
+        percent_used,
+        predicted_limit_time: predicted_limit_time.map(|dt| dt.to_rfc3339()),
+    }
+}
+
+fn gap_window(start: DateTime<Utc>, end: DateTime<Utc>) -> BillingWindow {
+    BillingWindow {
+        id: format!("gap-{}", start.to_rfc3339()),
+        start_time: start.to_rfc3339(),
+        end_time: end.to_rfc3339(),
+        total_tokens: 0,
+        cost_usd: 0.0,
+        remaining_minutes: 0,
+        is_active: false,
+        is_gap: true,
+        last_entry_time: None,
+        burn_rate: None,
+        projection: None,
+        limit: None,
+        percent_used: None,
+        predicted_limit_time: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn entry(timestamp: &str) -> LogEntry {
+        serde_json::from_value(serde_json::json!({
+            "timestamp": timestamp,
+            "message": {
+                "model": "claude-sonnet-4",
+                "usage": { "input_tokens": 1000, "output_tokens": 0 }
+            }
+        }))
+        .unwrap()
+    }
+
+    /// (start, end, is_gap, total_tokens)
+    type Expected = (&'static str, &'static str, bool, u64);
+
+    #[test]
+    fn splits_entries_into_blocks() {
+        let cases: &[(&str, &[&str], &[Expected])] = &[
+            ("no entries", &[], &[]),
+            (
+                "start is floored to the hour",
+                &["2025-06-01T10:37:00Z"],
+                &[("2025-06-01T10:00:00Z", "2025-06-01T15:00:00Z", false, 1000)],
+            ),
+            (
+                "entry just before the end stays in the block",
+                &["2025-06-01T10:30:00Z", "2025-06-01T14:59:59Z"],
+                &[("2025-06-01T10:00:00Z", "2025-06-01T15:00:00Z", false, 2000)],
+            ),
+            (
+                "entry at exactly start + 5h opens the next block",
+                &["2025-06-01T10:00:00Z", "2025-06-01T15:00:00Z"],
+                &[
+                    ("2025-06-01T10:00:00Z", "2025-06-01T15:00:00Z", false, 1000),
+                    ("2025-06-01T15:00:00Z", "2025-06-01T20:00:00Z", false, 1000),
+                ],
+            ),
+            (
+                "block ends at start + 5h, not 5h after the first entry",
+                &["2025-06-01T10:50:00Z", "2025-06-01T15:10:00Z"],
+                &[
+                    ("2025-06-01T10:00:00Z", "2025-06-01T15:00:00Z", false, 1000),
+                    ("2025-06-01T15:00:00Z", "2025-06-01T20:00:00Z", false, 1000),
+                ],
+            ),
+            (
+                "inactivity leaves a gap window",
+                &["2025-06-01T10:00:00Z", "2025-06-01T21:30:00Z"],
+                &[
+                    ("2025-06-01T10:00:00Z", "2025-06-01T15:00:00Z", false, 1000),
+                    ("2025-06-01T15:00:00Z", "2025-06-01T21:00:00Z", true, 0),
+                    ("2025-06-01T21:00:00Z", "2025-06-02T02:00:00Z", false, 1000),
+                ],
+            ),
+            (
+                "unsorted input is ordered first",
+                &["2025-06-01T12:00:00Z", "2025-06-01T10:15:00Z"],
+                &[("2025-06-01T10:00:00Z", "2025-06-01T15:00:00Z", false, 2000)],
+            ),
+            (
+                "entries without a valid timestamp are skipped",
+                &["not a timestamp", "2025-06-01T10:15:00Z"],
+                &[("2025-06-01T10:00:00Z", "2025-06-01T15:00:00Z", false, 1000)],
+            ),
+        ];
+
+        let now = ts("2025-07-01T00:00:00Z");
+        for (name, timestamps, expected) in cases {
+            let entries: Vec<LogEntry> = timestamps.iter().map(|t| entry(t)).collect();
+            let windows = calculate_billing_windows_at(&entries, now);
+
+            let actual: Vec<_> = windows
+                .iter()
+                .map(|w| (ts(&w.start_time), ts(&w.end_time), w.is_gap, w.total_tokens))
+                .collect();
+            let expected: Vec<_> = expected
+                .iter()
+                .map(|(start, end, is_gap, tokens)| (ts(start), ts(end), *is_gap, *tokens))
+                .collect();
+            assert_eq!(actual, expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn marks_only_the_current_block_active() {
+        let entries = vec![entry("2025-06-01T08:00:00Z"), entry("2025-06-01T13:20:00Z")];
+        let cases = [
+            (
+                "before any block",
+                "2025-06-01T07:59:00Z",
+                [false, false],
+                [301, 601],
+            ),
+            (
+                "inside the first block",
+                "2025-06-01T12:30:00Z",
+                [true, false],
+                [30, 330],
+            ),
+            (
+                "at the first block's end",
+                "2025-06-01T13:00:00Z",
+                [false, true],
+                [0, 300],
+            ),
+            (
+                "after every block",
+                "2025-06-01T18:00:00Z",
+                [false, false],
+                [0, 0],
+            ),
+        ];
+
+        for (name, now, active, remaining) in cases {
+            let windows = calculate_billing_windows_at(&entries, ts(now));
+            assert_eq!(windows.len(), 2, "{}", name);
+            let actual_active: Vec<bool> = windows.iter().map(|w| w.is_active).collect();
+            let actual_remaining: Vec<i64> = windows.iter().map(|w| w.remaining_minutes).collect();
+            assert_eq!(actual_active, active, "{}", name);
+            assert_eq!(actual_remaining, remaining, "{}", name);
+        }
+    }
+
+    /// (start, is_gap, is_active, total_tokens)
+    type RangeExpected = (&'static str, bool, bool, u64);
+
+    #[test]
+    fn keeps_windows_overlapping_the_range() {
+        let entries = vec![
+            entry("2025-06-01T13:10:00Z"),
+            entry("2025-06-01T22:40:00Z"),
+            entry("2025-06-02T01:30:00Z"),
+            entry("2025-06-02T09:00:00Z"),
+        ];
+        let now = ts("2025-06-02T02:00:00Z");
+        let tz = ReportTimeZone::parse(Some("UTC")).unwrap();
+
+        let cases: &[(&str, &str, &str, &[RangeExpected])] = &[
+            (
+                "block from before midnight is kept whole and active",
+                "2025-06-02",
+                "2025-06-02",
+                &[
+                    ("2025-06-01T22:00:00Z", false, true, 2000),
+                    ("2025-06-02T03:00:00Z", true, false, 0),
+                    ("2025-06-02T09:00:00Z", false, false, 1000),
+                ],
+            ),
+            (
+                "range ending mid-block keeps the block",
+                "2025-06-01T00:00:00Z",
+                "2025-06-01T23:00:00Z",
+                &[
+                    ("2025-06-01T13:00:00Z", false, false, 1000),
+                    ("2025-06-01T18:00:00Z", true, false, 0),
+                    ("2025-06-01T22:00:00Z", false, true, 2000),
+                ],
+            ),
+            (
+                "range inside a gap keeps only the gap",
+                "2025-06-02T04:00:00Z",
+                "2025-06-02T05:00:00Z",
+                &[("2025-06-02T03:00:00Z", true, false, 0)],
+            ),
+        ];
+
+        for (name, start, end, expected) in cases {
+            let range = TimeRange::parse(start, end, tz).unwrap();
+            let windows = billing_windows_for_range_at(&entries, &range, None, now);
+
+            let actual: Vec<_> = windows
+                .iter()
+                .map(|w| (ts(&w.start_time), w.is_gap, w.is_active, w.total_tokens))
+                .collect();
+            let expected: Vec<_> = expected
+                .iter()
+                .map(|(start, is_gap, is_active, tokens)| (ts(start), *is_gap, *is_active, *tokens))
+                .collect();
+            assert_eq!(actual, expected, "{}", name);
+        }
+    }
+
+    /// (tokens_per_minute, projected total_tokens), None when not active
+    type ProjectionExpected = Option<(f64, u64)>;
+
+    #[test]
+    fn projects_the_active_block_from_recent_usage() {
+        // Block runs 10:00-15:00 and each entry is 1000 tokens
+        let cases: &[(&str, &[&str], &str, ProjectionExpected)] = &[
+            (
+                "ten minutes in, rate since the block start",
+                &["2025-06-01T10:00:00Z", "2025-06-01T10:05:00Z"],
+                "2025-06-01T10:10:00Z",
+                Some((200.0, 2000 + 200 * 290)),
+            ),
+            (
+                "only the last 30 minutes count",
+                &[
+                    "2025-06-01T10:00:00Z",
+                    "2025-06-01T12:00:00Z",
+                    "2025-06-01T14:00:00Z",
+                    "2025-06-01T14:20:00Z",
+                ],
+                "2025-06-01T14:30:00Z",
+                Some((2000.0 / 30.0, 4000 + 2000)),
+            ),
+            (
+                "idle for the last 30 minutes projects no growth",
+                &["2025-06-01T10:00:00Z"],
+                "2025-06-01T11:00:00Z",
+                Some((0.0, 1000)),
+            ),
+            (
+                "finished block has no projection",
+                &["2025-06-01T10:00:00Z"],
+                "2025-06-01T15:00:00Z",
+                None,
+            ),
+        ];
+
+        for (name, timestamps, now, expected) in cases {
+            let entries: Vec<LogEntry> = timestamps.iter().map(|t| entry(t)).collect();
+            let windows = calculate_billing_windows_at(&entries, ts(now));
+            let window = &windows[0];
+
+            let actual = window
+                .burn_rate
+                .as_ref()
+                .zip(window.projection.as_ref())
+                .map(|(rate, projection)| (rate.tokens_per_minute, projection.total_tokens));
+            match (actual, expected) {
+                (Some((rate, tokens)), Some((expected_rate, expected_tokens))) => {
+                    assert!(
+                        (rate - expected_rate).abs() < 1e-9,
+                        "{}: rate {}",
+                        name,
+                        rate
+                    );
+                    assert_eq!(tokens, *expected_tokens, "{}", name);
+                    let projection = window.projection.as_ref().unwrap();
+                    assert!(projection.cost_usd >= window.cost_usd, "{}", name);
+                }
+                (None, None) => {}
+                (actual, expected) => panic!("{}: {:?} != {:?}", name, actual, expected),
+            }
+        }
+    }
+
+    /// (name, plan, entries in the active block, now, (percent_used,
+    /// predicted_limit_time))
+    type LimitCase = (
+        &'static str,
+        Option<Plan>,
+        &'static [&'static str],
+        &'static str,
+        (Option<f64>, Option<&'static str>),
+    );
+
+    #[test]
+    fn measures_blocks_against_the_cap() {
+        // A finished 2000-token block, then the active block from 10:00
+        let history = ["2025-06-01T01:00:00Z", "2025-06-01T01:30:00Z"];
+        let custom = |token_limit, cost_limit_usd| Plan::Custom {
+            token_limit,
+            cost_limit_usd,
+        };
+
+        let cases: &[LimitCase] = &[
+            (
+                "busiest finished block is the default cap",
+                None,
+                &["2025-06-01T10:00:00Z"],
+                "2025-06-01T10:10:00Z",
+                // 1000 of 2000 used at 100/min: capped 10 minutes from now
+                (Some(50.0), Some("2025-06-01T10:20:00Z")),
+            ),
+            (
+                "plan cap wins over history",
+                Some(custom(Some(4000), None)),
+                &["2025-06-01T10:00:00Z"],
+                "2025-06-01T10:10:00Z",
+                (Some(25.0), Some("2025-06-01T10:40:00Z")),
+            ),
+            (
+                "cap out of reach before the block ends",
+                Some(Plan::Pro),
+                &["2025-06-01T10:00:00Z"],
+                "2025-06-01T10:10:00Z",
+                (Some(1000.0 / PRO_TOKEN_LIMIT as f64 * 100.0), None),
+            ),
+            (
+                "cap already reached",
+                Some(custom(Some(2000), None)),
+                &["2025-06-01T10:00:00Z", "2025-06-01T10:05:00Z"],
+                "2025-06-01T10:10:00Z",
+                (Some(100.0), None),
+            ),
+            (
+                "custom plan without caps measures nothing",
+                Some(custom(None, None)),
+                &["2025-06-01T10:00:00Z"],
+                "2025-06-01T10:10:00Z",
+                (None, None),
+            ),
+        ];
+
+        let range = TimeRange::parse(
+            "2025-06-01T10:00:00Z",
+            "2025-06-01T15:00:00Z",
+            ReportTimeZone::Local,
+        )
+        .unwrap();
+        for (name, plan, timestamps, now, (percent, predicted)) in cases {
+            let entries: Vec<LogEntry> = history
+                .iter()
+                .chain(timestamps.iter())
+                .map(|t| entry(t))
+                .collect();
+            let windows = billing_windows_for_range_at(&entries, &range, plan.as_ref(), ts(now));
+            assert_eq!(windows.len(), 1, "{}", name);
+            let window = &windows[0];
+
+            match (window.percent_used, percent) {
+                (Some(actual), Some(expected)) => {
+                    assert!((actual - expected).abs() < 1e-9, "{}: {}", name, actual)
+                }
+                (actual, expected) => assert_eq!(actual, *expected, "{}", name),
+            }
+            assert_eq!(
+                window.predicted_limit_time.as_deref().map(ts),
+                predicted.map(ts),
+                "{}",
+                name
+            );
+        }
+    }
+}
@@ -1,33 +1,22 @@
+pub mod blocks;
+pub mod project;
+pub mod range;
+pub mod roots;
 pub mod types;
 
-use std::collections::HashMap;
+use std::collections::{hash_map, HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 
-use chrono::{DateTime, Duration, Utc};
+use chrono::{Duration, Utc};
 use walkdir::WalkDir;
 
-use crate::pricing::calculate_cost;
+use crate::pricing::{calculate_cost, price_request, RequestCost};
+use project::{project_dir_name, ProjectResolver};
+use range::{RangeError, ReportTimeZone, TimeRange};
 use types::*;
 
-pub fn get_claude_paths() -> Vec<PathBuf> {
-    let home = dirs::home_dir().expect("Could not find home directory");
-    let mut paths = Vec::new();
-
-    let claude_path = home.join(".claude/projects");
-    if claude_path.exists() {
-        paths.push(claude_path);
-    }
-
-    let config_path = home.join(".config/claude/projects");
-    if config_path.exists() {
-        paths.push(config_path);
-    }
-
-    paths
-}
-
 pub fn find_jsonl_files(paths: &[PathBuf]) -> Vec<PathBuf> {
     let mut files = Vec::new();
 
@@ -38,7 +27,7 @@ pub fn find_jsonl_files(paths: &[PathBuf]) -> Vec<PathBuf> {
             .filter_map(|e| e.ok())
         {
             let path = entry.path();
-            if path.extension().map_or(false, |ext| ext == "jsonl") {
+            if path.extension().is_some_and(|ext| ext == "jsonl") {
                 files.push(path.to_path_buf());
             }
         }
@@ -47,34 +36,146 @@ pub fn find_jsonl_files(paths: &[PathBuf]) -> Vec<PathBuf> {
     files
 }
 
-pub fn parse_jsonl_file(path: &PathBuf) -> Vec<LogEntry> {
-    let mut entries = Vec::new();
+/// Entries parsed from the tail of a JSONL file
+#[derive(Debug, Default)]
+pub struct ParsedChunk {
+    pub entries: Vec<LogEntry>,
+    /// Byte offset just past the last complete line that was read
+    pub end_offset: u64,
+    /// Line numbers in samples count from `offset`
+    pub stats: ParseStats,
+}
+
+/// Why a line that doesn't deserialize as a `LogEntry` was skipped
+fn rejection_reason(line: &str, error: &serde_json::Error) -> SkipReason {
+    if !error.is_data() {
+        return SkipReason::InvalidJson;
+    }
+
+    // Summaries and other bookkeeping lines carry no message at all
+    let has_message = serde_json::from_str::<serde_json::Value>(line)
+        .is_ok_and(|value| value.get("message").is_some());
+    if has_message {
+        SkipReason::InvalidEntry
+    } else {
+        SkipReason::NotAnEntry
+    }
+}
 
-    let file = match File::open(path) {
-        Ok(f) => f,
-        Err(_) => return entries,
+/// Parse complete lines starting at `offset`. A trailing line without a
+/// newline is still being written, so it is left for the next read.
+pub fn parse_jsonl_from(path: &Path, offset: u64) -> io::Result<ParsedChunk> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+
+    let project_dir = project_dir_name(path);
+    let mut reader = BufReader::new(file);
+    let mut chunk = ParsedChunk {
+        entries: Vec::new(),
+        end_offset: offset,
+        stats: ParseStats::default(),
     };
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 || line.last() != Some(&b'\n') {
+            break;
+        }
+        chunk.end_offset += read as u64;
+        chunk.stats.lines_read += 1;
+        let line_number = chunk.stats.lines_read;
+
+        let line_content = match std::str::from_utf8(&line) {
+            Ok(content) => content,
+            Err(e) => {
+                let text = String::from_utf8_lossy(&line);
+                let reason = SkipReason::InvalidUtf8;
+                chunk
+                    .stats
+                    .reject(reason, line_number, e.to_string(), &text);
+                continue;
+            }
+        };
+
+        match serde_json::from_str::<LogEntry>(line_content) {
+            Ok(mut entry) if entry.message.usage.is_some() => {
+                entry.project_dir = project_dir.clone();
+                chunk.entries.push(entry);
+                chunk.stats.entries_kept += 1;
+            }
+            Ok(_) => chunk.stats.skip(SkipReason::NoUsage),
+            Err(e) => {
+                let reason = rejection_reason(line_content, &e);
+                chunk
+                    .stats
+                    .reject(reason, line_number, e.to_string(), line_content);
+            }
+        }
+    }
 
-    let reader = BufReader::new(file);
+    Ok(chunk)
+}
+
+pub fn parse_jsonl_file(path: &Path) -> Vec<LogEntry> {
+    parse_jsonl_from(path, 0)
+        .map(|chunk| chunk.entries)
+        .unwrap_or_default()
+}
+
+/// How far a streamed copy of a message got: output tokens first, since
+/// those grow while streaming, then all tokens
+fn usage_progress(entry: &LogEntry) -> (u64, u64) {
+    entry
+        .message
+        .usage
+        .as_ref()
+        .map_or((0, 0), |usage| (usage.output_tokens, usage.total_tokens()))
+}
+
+/// Drop repeated copies of the same assistant message. Copies written while
+/// streaming can carry partial usage, so the one with the most tokens is
+/// kept, the earliest winning ties. The result is sorted by timestamp.
+pub fn dedupe_entries(mut entries: Vec<LogEntry>) -> Vec<LogEntry> {
+    entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
 
-    for line in reader.lines() {
-        if let Ok(line_content) = line {
-            if let Ok(entry) = serde_json::from_str::<LogEntry>(&line_content) {
-                if entry.message.usage.is_some() {
-                    entries.push(entry);
+    let mut kept: HashMap<(String, String), usize> = HashMap::new();
+    let mut deduped: Vec<LogEntry> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let Some(key) = entry.dedup_key() else {
+            deduped.push(entry);
+            continue;
+        };
+        match kept.entry(key) {
+            hash_map::Entry::Occupied(slot) => {
+                let existing = &mut deduped[*slot.get()];
+                if usage_progress(&entry) > usage_progress(existing) {
+                    *existing = entry;
                 }
             }
+            hash_map::Entry::Vacant(slot) => {
+                slot.insert(deduped.len());
+                deduped.push(entry);
+            }
         }
     }
 
-    entries
+    // A later copy may have replaced an earlier one
+    deduped.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    deduped
 }
 
+/// Filter entries by one of the named ranges, in the machine's time zone
+pub fn filter_entries_by_time_range(entries: &[LogEntry], time_range: &str) -> Vec<LogEntry> {
+    TimeRange::preset(time_range, ReportTimeZone::Local).filter(entries)
+}
 
 pub fn aggregate_usage(entries: &[LogEntry]) -> UsageData {
     let mut total_cost = 0.0;
     let mut total_tokens = AggregatedTokens::default();
     let mut model_map: HashMap<String, ModelUsage> = HashMap::new();
+    let mut unpriced_map: HashMap<String, UnpricedModel> = HashMap::new();
 
     for entry in entries {
         if let Some(ref usage) = entry.message.usage {
@@ -84,20 +185,38 @@ pub fn aggregate_usage(entries: &[LogEntry]) -> UsageData {
                 .clone()
                 .unwrap_or_else(|| "unknown".to_string());
 
-            let cost = entry.cost_usd.unwrap_or_else(|| calculate_cost(&model, usage));
+            let priced = match entry.cost_usd {
+                Some(cost_usd) => RequestCost {
+                    cost_usd,
+                    is_known: true,
+                },
+                None => price_request(&model, usage, &entry.timestamp),
+            };
+            let cost = priced.cost_usd;
             total_cost += cost;
 
-            total_tokens.input_tokens += usage.input_tokens;
-            total_tokens.output_tokens += usage.output_tokens;
-            total_tokens.cache_creation_input_tokens +=
-                usage.cache_creation_input_tokens.unwrap_or(0);
-            total_tokens.cache_read_input_tokens += usage.cache_read_input_tokens.unwrap_or(0);
+            total_tokens.add_usage(usage);
+
+            // Zero-token entries (e.g. synthetic messages) cost nothing either way
+            if !priced.is_known && usage.total_tokens() > 0 {
+                let unpriced = unpriced_map
+                    .entry(model.clone())
+                    .or_insert_with(|| UnpricedModel {
+                        model: model.clone(),
+                        tokens: AggregatedTokens::default(),
+                        estimated_cost_usd: 0.0,
+                    });
+                unpriced.tokens.add_usage(usage);
+                unpriced.estimated_cost_usd += cost;
+            }
 
             let model_usage = model_map.entry(model.clone()).or_insert(ModelUsage {
                 model: model.clone(),
                 input_tokens: 0,
                 output_tokens: 0,
                 cache_creation_input_tokens: 0,
+                cache_creation_5m_input_tokens: 0,
+                cache_creation_1h_input_tokens: 0,
                 cache_read_input_tokens: 0,
                 cost_usd: 0.0,
             });
@@ -106,138 +225,98 @@ pub fn aggregate_usage(entries: &[LogEntry]) -> UsageData {
             model_usage.output_tokens += usage.output_tokens;
             model_usage.cache_creation_input_tokens +=
                 usage.cache_creation_input_tokens.unwrap_or(0);
+            model_usage.cache_creation_5m_input_tokens += usage.cache_creation_5m_tokens();
+            model_usage.cache_creation_1h_input_tokens += usage.cache_creation_1h_tokens();
             model_usage.cache_read_input_tokens += usage.cache_read_input_tokens.unwrap_or(0);
             model_usage.cost_usd += cost;
         }
     }
 
     let model_breakdown: Vec<ModelUsage> = model_map.into_values().collect();
+    let unpriced_models: Vec<UnpricedModel> = unpriced_map.into_values().collect();
 
     UsageData {
         total_cost_usd: total_cost,
         total_tokens,
         model_breakdown,
+        unpriced_models,
         last_updated: Utc::now().to_rfc3339(),
     }
 }
 
-pub fn calculate_billing_windows(entries: &[LogEntry]) -> Vec<BillingWindow> {
-    if entries.is_empty() {
-        return Vec::new();
-    }
-
-    let mut windows: Vec<BillingWindow> = Vec::new();
-    let window_duration = Duration::hours(5);
-    let now = Utc::now();
+/// Ranges up to this long are charted per hour unless a granularity is given
+const HOURLY_SERIES_MAX_SPAN_HOURS: i64 = 48;
+
+/// Cost and tokens per calendar day (or hour) across `range`, with empty
+/// buckets included so charts have no gaps
+pub fn build_usage_series(
+    entries: &[LogEntry],
+    range: &TimeRange,
+    granularity: Option<SeriesGranularity>,
+) -> Result<UsageSeries, RangeError> {
+    let granularity = granularity.unwrap_or_else(|| {
+        let span = range.end.signed_duration_since(range.start);
+        if span <= Duration::hours(HOURLY_SERIES_MAX_SPAN_HOURS) {
+            SeriesGranularity::Hour
+        } else {
+            SeriesGranularity::Day
+        }
+    });
 
-    let mut current_window_start: Option<DateTime<Utc>> = None;
-    let mut window_entries: Vec<&LogEntry> = Vec::new();
+    let starts = range.bucket_starts(granularity)?;
+    let mut grouped: Vec<Vec<LogEntry>> = vec![Vec::new(); starts.len()];
 
     for entry in entries {
-        if let Ok(dt) = DateTime::parse_from_rfc3339(&entry.timestamp) {
-            let entry_time = dt.with_timezone(&Utc);
-
-            match current_window_start {
-                None => {
-                    current_window_start = Some(entry_time);
-                    window_entries.push(entry);
-                }
-                Some(start) => {
-                    if entry_time - start <= window_duration {
-                        window_entries.push(entry);
-                    } else {
-                        let end_time = start + window_duration;
-                        let remaining = (end_time - now).num_minutes().max(0);
-                        let is_active = now < end_time && now >= start;
-
-                        let mut total_tokens = 0u64;
-                        let mut total_cost = 0.0;
-
-                        for e in &window_entries {
-                            if let Some(ref usage) = e.message.usage {
-                                total_tokens += usage.input_tokens
-                                    + usage.output_tokens
-                                    + usage.cache_creation_input_tokens.unwrap_or(0)
-                                    + usage.cache_read_input_tokens.unwrap_or(0);
-
-                                let model = e
-                                    .message
-                                    .model
-                                    .clone()
-                                    .unwrap_or_else(|| "unknown".to_string());
-                                total_cost +=
-                                    e.cost_usd.unwrap_or_else(|| calculate_cost(&model, usage));
-                            }
-                        }
-
-                        windows.push(BillingWindow {
-                            id: start.to_rfc3339(),
-                            start_time: start.to_rfc3339(),
-                            end_time: end_time.to_rfc3339(),
-                            total_tokens,
-                            cost_usd: total_cost,
-                            remaining_minutes: remaining,
-                            is_active,
-                        });
-
-                        current_window_start = Some(entry_time);
-                        window_entries.clear();
-                        window_entries.push(entry);
-                    }
-                }
+        if let Some(dt) = entry.parsed_timestamp() {
+            if !range.contains(dt) {
+                continue;
+            }
+            // Last bucket starting at or before the entry
+            let idx = starts.partition_point(|start| *start <= dt);
+            if idx > 0 {
+                grouped[idx - 1].push(entry.clone());
             }
         }
     }
 
-    if let Some(start) = current_window_start {
-        if !window_entries.is_empty() {
-            let end_time = start + window_duration;
-            let remaining = (end_time - now).num_minutes().max(0);
-            let is_active = now < end_time && now >= start;
-
-            let mut total_tokens = 0u64;
-            let mut total_cost = 0.0;
-
-            for e in &window_entries {
-                if let Some(ref usage) = e.message.usage {
-                    total_tokens += usage.input_tokens
-                        + usage.output_tokens
-                        + usage.cache_creation_input_tokens.unwrap_or(0)
-                        + usage.cache_read_input_tokens.unwrap_or(0);
-
-                    let model = e
-                        .message
-                        .model
-                        .clone()
-                        .unwrap_or_else(|| "unknown".to_string());
-                    total_cost += e.cost_usd.unwrap_or_else(|| calculate_cost(&model, usage));
-                }
-            }
+    let label_format = match granularity {
+        SeriesGranularity::Hour => "%Y-%m-%d %H:00",
+        SeriesGranularity::Day => "%Y-%m-%d",
+    };
 
-            windows.push(BillingWindow {
-                id: start.to_rfc3339(),
+    let buckets = starts
+        .iter()
+        .enumerate()
+        .map(|(i, start)| {
+            let end = starts.get(i + 1).copied().unwrap_or(range.end);
+            let usage = aggregate_usage(&grouped[i]);
+            SeriesBucket {
                 start_time: start.to_rfc3339(),
-                end_time: end_time.to_rfc3339(),
-                total_tokens,
-                cost_usd: total_cost,
-                remaining_minutes: remaining,
-                is_active,
-            });
-        }
-    }
+                end_time: end.to_rfc3339(),
+                label: range.tz.naive_of(*start).format(label_format).to_string(),
+                cost_usd: usage.total_cost_usd,
+                tokens: usage.total_tokens,
+                model_breakdown: usage.model_breakdown,
+            }
+        })
+        .collect();
 
-    windows
+    Ok(UsageSeries {
+        granularity,
+        buckets,
+    })
 }
 
 pub fn get_session_breakdown(entries: &[LogEntry]) -> Vec<SessionSummary> {
     let mut session_map: HashMap<String, SessionSummary> = HashMap::new();
+    let mut projects = ProjectResolver::new();
 
     for entry in entries {
         let session_id = entry
             .session_id
             .clone()
             .unwrap_or_else(|| "unknown".to_string());
-        let project_path = entry.cwd.clone().unwrap_or_else(|| "unknown".to_string());
+        let project_path = projects.project_path(entry);
 
         let model = entry
             .message
@@ -248,7 +327,7 @@ pub fn get_session_breakdown(entries: &[LogEntry]) -> Vec<SessionSummary> {
         let cost = if let Some(ref usage) = entry.message.usage {
             entry
                 .cost_usd
-                .unwrap_or_else(|| calculate_cost(&model, usage))
+                .unwrap_or_else(|| calculate_cost(&model, usage, &entry.timestamp))
         } else {
             0.0
         };
@@ -278,3 +357,354 @@ pub fn get_session_breakdown(entries: &[LogEntry]) -> Vec<SessionSummary> {
     sessions.sort_by(|a, b| b.end_time.cmp(&a.end_time));
     sessions
 }
+
+/// Roll usage up by project, keyed by the entry's working directory or, when
+/// that is missing, the project directory the log was found in
+pub fn get_project_breakdown(entries: &[LogEntry]) -> Vec<ProjectSummary> {
+    let mut project_map: HashMap<String, (ProjectSummary, HashSet<String>)> = HashMap::new();
+    let mut projects = ProjectResolver::new();
+
+    for entry in entries {
+        let Some(ref usage) = entry.message.usage else {
+            continue;
+        };
+        let project_path = projects.project_path(entry);
+        let model = entry
+            .message
+            .model
+            .clone()
+            .unwrap_or_else(|| "unknown".to_string());
+        let cost = entry
+            .cost_usd
+            .unwrap_or_else(|| calculate_cost(&model, usage, &entry.timestamp));
+
+        let (project, sessions) = project_map.entry(project_path.clone()).or_insert_with(|| {
+            (
+                ProjectSummary {
+                    project_path,
+                    total_cost_usd: 0.0,
+                    tokens: AggregatedTokens::default(),
+                    session_count: 0,
+                    message_count: 0,
+                    last_activity: entry.timestamp.clone(),
+                },
+                HashSet::new(),
+            )
+        });
+
+        project.total_cost_usd += cost;
+        project.tokens.add_usage(usage);
+        project.message_count += 1;
+        if entry.timestamp > project.last_activity {
+            project.last_activity = entry.timestamp.clone();
+        }
+        if let Some(ref session_id) = entry.session_id {
+            sessions.insert(session_id.clone());
+        }
+    }
+
+    let mut summaries: Vec<ProjectSummary> = project_map
+        .into_values()
+        .map(|(mut project, sessions)| {
+            project.session_count = sessions.len() as u32;
+            project
+        })
+        .collect();
+    summaries.sort_by(|a, b| b.total_cost_usd.total_cmp(&a.total_cost_usd));
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use range::MAX_SERIES_BUCKETS;
+
+    fn entry(
+        timestamp: &str,
+        message_id: Option<&str>,
+        request_id: Option<&str>,
+        output_tokens: u64,
+        project_dir: &str,
+    ) -> LogEntry {
+        let mut entry: LogEntry = serde_json::from_value(serde_json::json!({
+            "timestamp": timestamp,
+            "requestId": request_id,
+            "message": {
+                "id": message_id,
+                "model": "claude-sonnet-4",
+                "usage": { "input_tokens": 10, "output_tokens": output_tokens }
+            }
+        }))
+        .unwrap();
+        entry.project_dir = Some(project_dir.to_string());
+        entry
+    }
+
+    /// (timestamp, output_tokens, project_dir) of a kept entry
+    type Kept = (&'static str, u64, &'static str);
+
+    #[test]
+    fn dedupes_copies_of_a_message() {
+        let cases: &[(&str, Vec<LogEntry>, &[Kept])] = &[
+            (
+                "copy in a resumed session's file is counted once",
+                vec![
+                    entry("2025-06-01T10:00:00Z", Some("m1"), Some("r1"), 50, "-a"),
+                    entry("2025-06-01T11:00:00Z", Some("m1"), Some("r1"), 50, "-b"),
+                ],
+                &[("2025-06-01T10:00:00Z", 50, "-a")],
+            ),
+            (
+                "final streamed copy wins over a partial one",
+                vec![
+                    entry("2025-06-01T10:00:00Z", Some("m1"), Some("r1"), 1, "-a"),
+                    entry("2025-06-01T10:00:05Z", Some("m1"), Some("r1"), 400, "-a"),
+                    entry("2025-06-01T10:00:02Z", Some("m1"), Some("r1"), 120, "-a"),
+                ],
+                &[("2025-06-01T10:00:05Z", 400, "-a")],
+            ),
+            (
+                "result stays sorted when a later copy is kept",
+                vec![
+                    entry("2025-06-01T10:00:00Z", Some("m1"), Some("r1"), 1, "-a"),
+                    entry("2025-06-01T10:00:01Z", Some("m2"), Some("r2"), 7, "-a"),
+                    entry("2025-06-01T10:00:05Z", Some("m1"), Some("r1"), 400, "-a"),
+                ],
+                &[
+                    ("2025-06-01T10:00:01Z", 7, "-a"),
+                    ("2025-06-01T10:00:05Z", 400, "-a"),
+                ],
+            ),
+            (
+                "same message id under another request is kept",
+                vec![
+                    entry("2025-06-01T10:00:00Z", Some("m1"), Some("r1"), 50, "-a"),
+                    entry("2025-06-01T10:00:01Z", Some("m1"), Some("r2"), 60, "-a"),
+                ],
+                &[
+                    ("2025-06-01T10:00:00Z", 50, "-a"),
+                    ("2025-06-01T10:00:01Z", 60, "-a"),
+                ],
+            ),
+            (
+                "missing request id is never deduplicated",
+                vec![
+                    entry("2025-06-01T10:00:00Z", Some("m1"), None, 50, "-a"),
+                    entry("2025-06-01T10:00:01Z", Some("m1"), None, 50, "-b"),
+                ],
+                &[
+                    ("2025-06-01T10:00:00Z", 50, "-a"),
+                    ("2025-06-01T10:00:01Z", 50, "-b"),
+                ],
+            ),
+            (
+                "missing message id is never deduplicated",
+                vec![
+                    entry("2025-06-01T10:00:00Z", None, Some("r1"), 50, "-a"),
+                    entry("2025-06-01T10:00:01Z", None, Some("r1"), 50, "-a"),
+                ],
+                &[
+                    ("2025-06-01T10:00:00Z", 50, "-a"),
+                    ("2025-06-01T10:00:01Z", 50, "-a"),
+                ],
+            ),
+        ];
+
+        for (name, entries, expected) in cases {
+            let kept: Vec<(String, u64, String)> = dedupe_entries(entries.clone())
+                .into_iter()
+                .map(|e| {
+                    let output = e.message.usage.as_ref().map_or(0, |u| u.output_tokens);
+                    (e.timestamp, output, e.project_dir.unwrap_or_default())
+                })
+                .collect();
+            let expected: Vec<(String, u64, String)> = expected
+                .iter()
+                .map(|(ts, output, dir)| (ts.to_string(), *output, dir.to_string()))
+                .collect();
+            assert_eq!(kept, expected, "{}", name);
+        }
+    }
+
+    fn utc_range(start: &str, end: &str, tz: &str) -> TimeRange {
+        TimeRange::parse(start, end, ReportTimeZone::parse(Some(tz)).unwrap()).unwrap()
+    }
+
+    /// (label, hours long, output tokens) of each bucket
+    fn bucket_summary(series: &UsageSeries) -> Vec<(String, i64, u64)> {
+        series
+            .buckets
+            .iter()
+            .map(|bucket| {
+                let start = chrono::DateTime::parse_from_rfc3339(&bucket.start_time).unwrap();
+                let end = chrono::DateTime::parse_from_rfc3339(&bucket.end_time).unwrap();
+                (
+                    bucket.label.clone(),
+                    (end - start).num_hours(),
+                    bucket.tokens.output_tokens,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn series_includes_empty_buckets() {
+        let entries = [
+            entry("2025-06-01T09:00:00Z", None, None, 5, "-a"),
+            entry("2025-06-03T23:59:59Z", None, None, 7, "-a"),
+            // Outside the range
+            entry("2025-06-04T00:00:00Z", None, None, 100, "-a"),
+        ];
+        let range = utc_range("2025-06-01", "2025-06-03", "UTC");
+
+        let series = build_usage_series(&entries, &range, None).unwrap();
+        assert_eq!(series.granularity, SeriesGranularity::Day);
+        assert_eq!(
+            bucket_summary(&series),
+            [
+                ("2025-06-01".to_string(), 24, 5),
+                ("2025-06-02".to_string(), 24, 0),
+                ("2025-06-03".to_string(), 24, 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn series_granularity_follows_the_range_length() {
+        // (start, end, requested, expected granularity, expected buckets)
+        let cases = [
+            (
+                "2025-06-01T00:00:00Z",
+                "2025-06-01T06:00:00Z",
+                None,
+                SeriesGranularity::Hour,
+                6,
+            ),
+            (
+                "2025-06-01T00:00:00Z",
+                "2025-06-03T00:00:00Z",
+                None,
+                SeriesGranularity::Hour,
+                48,
+            ),
+            (
+                "2025-06-01T00:00:00Z",
+                "2025-06-03T01:00:00Z",
+                None,
+                SeriesGranularity::Day,
+                3,
+            ),
+            (
+                "2025-06-01T00:00:00Z",
+                "2025-06-01T06:00:00Z",
+                Some(SeriesGranularity::Day),
+                SeriesGranularity::Day,
+                1,
+            ),
+            (
+                "2025-06-01T00:00:00Z",
+                "2025-06-08T00:00:00Z",
+                Some(SeriesGranularity::Hour),
+                SeriesGranularity::Hour,
+                168,
+            ),
+        ];
+
+        for (start, end, requested, granularity, buckets) in cases {
+            let range = utc_range(start, end, "UTC");
+            let series = build_usage_series(&[], &range, requested).unwrap();
+            assert_eq!(
+                (series.granularity, series.buckets.len()),
+                (granularity, buckets),
+                "{} to {} ({:?})",
+                start,
+                end,
+                requested
+            );
+        }
+    }
+
+    #[test]
+    fn series_is_capped_at_max_buckets() {
+        let start = utc_range("2025-01-01", "2025-01-01", "UTC").start;
+        let hours_range = |hours: i64| TimeRange {
+            start,
+            end: start + Duration::hours(hours),
+            tz: ReportTimeZone::Iana(chrono_tz::UTC),
+        };
+        let max = MAX_SERIES_BUCKETS as i64;
+
+        let series =
+            build_usage_series(&[], &hours_range(max - 1), Some(SeriesGranularity::Hour)).unwrap();
+        assert_eq!(series.buckets.len(), MAX_SERIES_BUCKETS - 1);
+
+        for (range, granularity) in [
+            (hours_range(max), SeriesGranularity::Hour),
+            (hours_range(max * 24), SeriesGranularity::Day),
+            (
+                TimeRange::preset("all", ReportTimeZone::Local),
+                SeriesGranularity::Day,
+            ),
+        ] {
+            assert!(
+                matches!(
+                    build_usage_series(&[], &range, Some(granularity)),
+                    Err(RangeError::TooManyBuckets(_))
+                ),
+                "{:?} {:?}",
+                range,
+                granularity
+            );
+        }
+    }
+
+    #[test]
+    fn day_buckets_follow_dst_changes() {
+        let entries = [
+            // 23:30 on the 29th in Berlin
+            entry("2025-03-29T22:30:00Z", None, None, 1, "-a"),
+            // 00:30 on the 31st in Berlin, after the clocks went forward
+            entry("2025-03-30T22:30:00Z", None, None, 2, "-a"),
+            // 00:30 on the 27th in Berlin, after the clocks went back
+            entry("2025-10-26T23:30:00Z", None, None, 3, "-a"),
+        ];
+        // (start, end, expected (label, hours, output tokens))
+        let cases = [
+            (
+                "2025-03-29",
+                "2025-03-31",
+                [
+                    ("2025-03-29", 24, 1),
+                    ("2025-03-30", 23, 0),
+                    ("2025-03-31", 24, 2),
+                ],
+            ),
+            (
+                "2025-10-25",
+                "2025-10-27",
+                [
+                    ("2025-10-25", 24, 0),
+                    ("2025-10-26", 25, 0),
+                    ("2025-10-27", 24, 3),
+                ],
+            ),
+        ];
+
+        for (start, end, expected) in cases {
+            let range = utc_range(start, end, "Europe/Berlin");
+            let series =
+                build_usage_series(&entries, &range, Some(SeriesGranularity::Day)).unwrap();
+            let expected: Vec<(String, i64, u64)> = expected
+                .iter()
+                .map(|(label, hours, output)| (label.to_string(), *hours, *output))
+                .collect();
+            assert_eq!(bucket_summary(&series), expected, "{} to {}", start, end);
+        }
+
+        let range = utc_range("2025-03-30", "2025-03-30", "Europe/Berlin");
+        let series = build_usage_series(&entries, &range, Some(SeriesGranularity::Hour)).unwrap();
+        let labels: Vec<&str> = series.buckets.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels.len(), 23);
+        assert_eq!(labels[1..3], ["2025-03-30 01:00", "2025-03-30 03:00"]);
+    }
+}
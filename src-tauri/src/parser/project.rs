@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use super::types::LogEntry;
+
+/// Name of the `projects/<encoded-path>` directory a log file lives under.
+/// Subagent logs sit deeper inside it, so this is not just the parent. The
+/// last `projects` component is used in case the home path has one too.
+pub fn project_dir_name(path: &Path) -> Option<String> {
+    let components: Vec<Component> = path.components().collect();
+    let idx = components
+        .iter()
+        .rposition(|c| *c == Component::Normal("projects".as_ref()))?;
+
+    // Need both a project directory and a file beneath it
+    if idx + 2 >= components.len() {
+        return None;
+    }
+    match components[idx + 1] {
+        Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+        _ => None,
+    }
+}
+
+/// Strip trailing separators so "/a/b/" and "/a/b" group together
+pub fn normalize_cwd(cwd: &str) -> String {
+    let trimmed = cwd.trim().trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() {
+        cwd.trim().to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// How Claude Code writes a path in a project directory name: `/`, `.` and
+/// anything else but ASCII letters and digits become `-`
+fn encode_path(path: &str) -> String {
+    path.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Find an existing path under `dir` that encodes to `encoded`, trying the
+/// entries of each directory on the way down
+fn find_encoded(dir: &Path, encoded: &str) -> Option<PathBuf> {
+    if encoded.is_empty() {
+        return Some(dir.to_path_buf());
+    }
+
+    let mut children: Vec<(String, PathBuf)> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| (encode_path(&e.file_name().to_string_lossy()), e.path()))
+        .collect();
+    children.sort();
+
+    if let Some((_, path)) = children.iter().find(|(name, _)| name == encoded) {
+        return Some(path.clone());
+    }
+    children.into_iter().find_map(|(name, path)| {
+        let rest = encoded.strip_prefix(name.as_str())?.strip_prefix('-')?;
+        if path.is_dir() {
+            find_encoded(&path, rest)
+        } else {
+            None
+        }
+    })
+}
+
+/// Recover a path from Claude Code's project directory name, which replaces
+/// every `/` and `.` with `-`. Hyphens and dots inside directory names make
+/// this ambiguous, so the directories on disk are used to decide. Falls back
+/// to treating every `-` as a separator.
+pub fn decode_project_dir(name: &str) -> String {
+    let encoded = name.strip_prefix('-').unwrap_or(name);
+    match find_encoded(Path::new("/"), encoded) {
+        Some(path) => path.to_string_lossy().into_owned(),
+        None => format!("/{}", encoded.replace('-', "/")),
+    }
+}
+
+/// Works out which project an entry belongs to, caching directory-name
+/// decodes since they touch the filesystem
+#[derive(Default)]
+pub struct ProjectResolver {
+    decoded: HashMap<String, String>,
+}
+
+impl ProjectResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The entry's `cwd`, else its decoded project directory, else "unknown"
+    pub fn project_path(&mut self, entry: &LogEntry) -> String {
+        if let Some(cwd) = entry.cwd.as_deref().filter(|c| !c.trim().is_empty()) {
+            return normalize_cwd(cwd);
+        }
+
+        match &entry.project_dir {
+            Some(dir) => self
+                .decoded
+                .entry(dir.clone())
+                .or_insert_with(|| decode_project_dir(dir))
+                .clone(),
+            None => "unknown".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_project_dirs_against_the_disk() {
+        // The temporary directory's own name starts with a dot
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let cases = [
+            ("dot in a directory", "src/foo.bar"),
+            ("dot in a parent", "a.b/project"),
+            ("hyphen in a directory", "my-app/sub"),
+            ("hidden directory", ".config/tool"),
+            ("underscore", "snake_case/x"),
+            ("dots and hyphens", "v1.2-beta/app"),
+        ];
+        for (_, path) in cases {
+            fs::create_dir_all(root.join(path)).unwrap();
+        }
+        // A sibling that only matches part of the way down
+        fs::create_dir_all(root.join("a-b")).unwrap();
+
+        for (name, path) in cases {
+            let expected = root.join(path);
+            let encoded = encode_path(&expected.to_string_lossy());
+            assert_eq!(
+                decode_project_dir(&encoded),
+                expected.to_string_lossy(),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn missing_project_dirs_split_on_every_hyphen() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir
+            .path()
+            .canonicalize()
+            .unwrap()
+            .join("gone.app/my-project");
+        let encoded = encode_path(&missing.to_string_lossy());
+
+        assert_eq!(
+            decode_project_dir(&encoded),
+            format!("/{}", encoded.trim_start_matches('-').replace('-', "/"))
+        );
+    }
+}
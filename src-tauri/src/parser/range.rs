@@ -0,0 +1,339 @@
+use chrono::{
+    DateTime, Datelike, Days, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc,
+};
+use chrono_tz::Tz;
+use thiserror::Error;
+
+use super::types::{LogEntry, SeriesGranularity};
+
+#[derive(Debug, Error)]
+pub enum RangeError {
+    #[error("unknown time zone {0:?}, expected an IANA name such as \"UTC\" or \"Europe/Berlin\"")]
+    UnknownTimeZone(String),
+    #[error("invalid date {0:?}, expected RFC 3339 (2025-01-31T09:00:00Z) or a date (2025-01-31)")]
+    InvalidBound(String),
+    #[error("date {0} is too far in the past or future")]
+    OutOfRange(NaiveDate),
+    #[error("range start {start} is not before its end {end}")]
+    Empty {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+    #[error("range is open-ended or too long for a series ({0} buckets, at most {max})", max = MAX_SERIES_BUCKETS)]
+    TooManyBuckets(usize),
+}
+
+/// Upper bound on buckets in one series, a bit over a year of hours
+pub const MAX_SERIES_BUCKETS: usize = 10_000;
+
+/// Zone used to decide which calendar day an entry belongs to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportTimeZone {
+    Local,
+    Iana(Tz),
+}
+
+impl ReportTimeZone {
+    /// Parse an IANA zone name. Missing, empty or "local" means the
+    /// machine's zone.
+    pub fn parse(name: Option<&str>) -> Result<Self, RangeError> {
+        match name.map(str::trim) {
+            None | Some("") => Ok(ReportTimeZone::Local),
+            Some(name) if name.eq_ignore_ascii_case("local") => Ok(ReportTimeZone::Local),
+            Some(name) => name
+                .parse::<Tz>()
+                .map(ReportTimeZone::Iana)
+                .map_err(|_| RangeError::UnknownTimeZone(name.to_string())),
+        }
+    }
+
+    pub fn date_of(&self, dt: DateTime<Utc>) -> NaiveDate {
+        self.naive_of(dt).date()
+    }
+
+    /// Wall-clock time of `dt` in this zone
+    pub fn naive_of(&self, dt: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            ReportTimeZone::Local => dt.with_timezone(&Local).naive_local(),
+            ReportTimeZone::Iana(tz) => dt.with_timezone(tz).naive_local(),
+        }
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.date_of(Utc::now())
+    }
+
+    /// Instant a wall-clock time occurs in this zone. Times skipped by a DST
+    /// jump resolve to the first valid instant after them.
+    pub fn resolve(&self, naive: NaiveDateTime) -> DateTime<Utc> {
+        match self {
+            ReportTimeZone::Local => resolve_in(&Local, naive),
+            ReportTimeZone::Iana(tz) => resolve_in(tz, naive),
+        }
+    }
+
+    pub fn start_of_day(&self, date: NaiveDate) -> DateTime<Utc> {
+        self.resolve(date.and_hms_opt(0, 0, 0).unwrap())
+    }
+}
+
+fn resolve_in<Z: TimeZone>(tz: &Z, naive: NaiveDateTime) -> DateTime<Utc> {
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(naive + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|| naive.and_utc())
+}
+
+/// Half-open `[start, end)` span of time, with the zone used to interpret
+/// calendar days inside it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeRange {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub tz: ReportTimeZone,
+}
+
+impl TimeRange {
+    /// The named ranges used by the dashboard: "today", "week" (the last
+    /// seven days plus today) and "month" (since the 1st). Anything else
+    /// covers all time.
+    pub fn preset(name: &str, tz: ReportTimeZone) -> Self {
+        let today = tz.today();
+        let start_date = match name {
+            "today" => Some(today),
+            "week" => Some(today - Duration::days(7)),
+            "month" => NaiveDate::from_ymd_opt(today.year(), today.month(), 1),
+            _ => None,
+        };
+        let end = match name {
+            "today" => tz.start_of_day(today + Duration::days(1)),
+            _ => DateTime::<Utc>::MAX_UTC,
+        };
+
+        TimeRange {
+            start: start_date.map_or(DateTime::<Utc>::MIN_UTC, |d| tz.start_of_day(d)),
+            end,
+            tz,
+        }
+    }
+
+    /// Build a range from explicit bounds. Each bound is either an RFC 3339
+    /// instant or a plain date in `tz`; a plain end date is inclusive.
+    pub fn parse(start: &str, end: &str, tz: ReportTimeZone) -> Result<Self, RangeError> {
+        let start = parse_bound(start, tz, false)?;
+        let end = parse_bound(end, tz, true)?;
+
+        if start >= end {
+            return Err(RangeError::Empty { start, end });
+        }
+
+        Ok(TimeRange { start, end, tz })
+    }
+
+    pub fn contains(&self, dt: DateTime<Utc>) -> bool {
+        dt >= self.start && dt < self.end
+    }
+
+    pub fn filter(&self, entries: &[LogEntry]) -> Vec<LogEntry> {
+        entries
+            .iter()
+            .filter(|entry| entry.parsed_timestamp().is_some_and(|dt| self.contains(dt)))
+            .cloned()
+            .collect()
+    }
+
+    /// Start instants of consecutive buckets covering the range. Day buckets
+    /// follow the zone's midnights, so DST days are 23 or 25 hours long.
+    pub fn bucket_starts(
+        &self,
+        granularity: SeriesGranularity,
+    ) -> Result<Vec<DateTime<Utc>>, RangeError> {
+        let span = self.end.signed_duration_since(self.start);
+        let estimate = match granularity {
+            SeriesGranularity::Hour => span.num_hours(),
+            SeriesGranularity::Day => span.num_days(),
+        };
+        if estimate < 0 || estimate as usize >= MAX_SERIES_BUCKETS {
+            return Err(RangeError::TooManyBuckets(estimate.max(0) as usize));
+        }
+
+        let mut starts = Vec::new();
+        match granularity {
+            SeriesGranularity::Hour => {
+                let local = self.tz.naive_of(self.start);
+                let floored = local.date().and_hms_opt(local.hour(), 0, 0).unwrap();
+                let mut start = self.tz.resolve(floored);
+                while start < self.end {
+                    starts.push(start);
+                    start += Duration::hours(1);
+                }
+            }
+            SeriesGranularity::Day => {
+                let mut date = self.tz.date_of(self.start);
+                let mut start = self.tz.start_of_day(date);
+                while start < self.end {
+                    starts.push(start);
+                    date += Duration::days(1);
+                    start = self.tz.start_of_day(date);
+                }
+            }
+        }
+
+        Ok(starts)
+    }
+}
+
+fn parse_bound(value: &str, tz: ReportTimeZone, is_end: bool) -> Result<DateTime<Utc>, RangeError> {
+    let value = value.trim();
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| RangeError::InvalidBound(value.to_string()))?;
+    if is_end {
+        let next = date
+            .checked_add_days(Days::new(1))
+            .ok_or(RangeError::OutOfRange(date))?;
+        Ok(tz.start_of_day(next))
+    } else {
+        Ok(tz.start_of_day(date))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn zone(name: &str) -> ReportTimeZone {
+        ReportTimeZone::parse(Some(name)).unwrap()
+    }
+
+    #[test]
+    fn parses_explicit_ranges() {
+        // (name, start, end, zone, expected start, expected end)
+        let cases = [
+            (
+                "date end is inclusive",
+                "2025-01-01",
+                "2025-01-31",
+                "UTC",
+                "2025-01-01T00:00:00Z",
+                "2025-02-01T00:00:00Z",
+            ),
+            (
+                "RFC 3339 end is the exact instant",
+                "2025-01-01",
+                "2025-01-31T00:00:00Z",
+                "UTC",
+                "2025-01-01T00:00:00Z",
+                "2025-01-31T00:00:00Z",
+            ),
+            (
+                "RFC 3339 offsets are kept whatever the zone",
+                "2025-01-01T10:00:00+02:00",
+                "2025-01-01",
+                "Asia/Tokyo",
+                "2025-01-01T08:00:00Z",
+                "2025-01-01T15:00:00Z",
+            ),
+            (
+                "dates start at midnight in the IANA zone",
+                "2025-01-01",
+                "2025-01-01",
+                "Europe/Berlin",
+                "2024-12-31T23:00:00Z",
+                "2025-01-01T23:00:00Z",
+            ),
+            (
+                "spring-forward day is 23 hours",
+                "2025-03-30",
+                "2025-03-30",
+                "Europe/Berlin",
+                "2025-03-29T23:00:00Z",
+                "2025-03-30T22:00:00Z",
+            ),
+            (
+                "fall-back day is 25 hours",
+                "2025-11-02",
+                "2025-11-02",
+                "America/New_York",
+                "2025-11-02T04:00:00Z",
+                "2025-11-03T05:00:00Z",
+            ),
+        ];
+
+        for (name, start, end, tz, expected_start, expected_end) in cases {
+            let range = TimeRange::parse(start, end, zone(tz)).unwrap();
+            assert_eq!(
+                (range.start, range.end),
+                (ts(expected_start), ts(expected_end)),
+                "{}",
+                name
+            );
+        }
+    }
+
+    /// (name, start, end, whether the error is the expected one)
+    type BadRange<'a> = (&'a str, &'a str, &'a str, fn(&RangeError) -> bool);
+
+    #[test]
+    fn rejects_bad_ranges() {
+        let last_day = NaiveDate::MAX.to_string();
+        let cases: [BadRange; 4] = [
+            ("not a date", "2025-01-01", "soon", |e| {
+                matches!(e, RangeError::InvalidBound(_))
+            }),
+            ("end before start", "2025-02-01", "2025-01-01", |e| {
+                matches!(e, RangeError::Empty { .. })
+            }),
+            (
+                "same instant",
+                "2025-01-01T00:00:00Z",
+                "2025-01-01T00:00:00Z",
+                |e| matches!(e, RangeError::Empty { .. }),
+            ),
+            (
+                "no day after the last one",
+                "2025-01-01",
+                &last_day,
+                |e| matches!(e, RangeError::OutOfRange(date) if *date == NaiveDate::MAX),
+            ),
+        ];
+
+        for (name, start, end, expected) in cases {
+            let error = TimeRange::parse(start, end, zone("UTC")).unwrap_err();
+            assert!(expected(&error), "{}: {:?}", name, error);
+        }
+    }
+
+    #[test]
+    fn parses_time_zones() {
+        let cases = [
+            (None, Some(ReportTimeZone::Local)),
+            (Some(" "), Some(ReportTimeZone::Local)),
+            (Some("LOCAL"), Some(ReportTimeZone::Local)),
+            (Some("UTC"), Some(ReportTimeZone::Iana(Tz::UTC))),
+            (
+                Some("Europe/Berlin"),
+                Some(ReportTimeZone::Iana(Tz::Europe__Berlin)),
+            ),
+            (Some("Mars/Olympus_Mons"), None),
+        ];
+
+        for (name, expected) in cases {
+            assert_eq!(ReportTimeZone::parse(name).ok(), expected, "{:?}", name);
+        }
+    }
+}
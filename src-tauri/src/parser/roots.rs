@@ -0,0 +1,191 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Comma-separated Claude config directories whose `projects` folders hold
+/// the logs. When set, the default locations are not searched.
+pub const CONFIG_DIR_ENV: &str = "CLAUDE_CONFIG_DIR";
+
+/// Config directories under the home directory, either of which may exist
+const DEFAULT_CONFIG_DIRS: [&str; 2] = [".claude", ".config/claude"];
+
+/// Directories searched for JSONL logs
+#[derive(Debug, Clone, Default)]
+pub struct LogRoots {
+    /// Existing roots, canonicalized so a symlinked root is only listed once
+    pub found: Vec<PathBuf>,
+    /// Roots that don't exist. A missing default location is only listed
+    /// when neither default exists.
+    pub missing: Vec<PathBuf>,
+}
+
+impl LogRoots {
+    /// Add `path` if it exists and isn't already listed under another name
+    fn add(&mut self, path: &Path, seen: &mut HashSet<PathBuf>) -> bool {
+        match fs::canonicalize(path) {
+            Ok(real) if real.is_dir() => {
+                if seen.insert(real.clone()) {
+                    self.found.push(real);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn report_missing(&self) {
+        for path in &self.missing {
+            eprintln!("Log directory not found: {:?}", path);
+        }
+    }
+}
+
+/// Expand a leading `~` to the home directory
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Directories listed in a `CLAUDE_CONFIG_DIR` value, or `None` if it lists
+/// none
+fn parse_config_dirs(value: &str) -> Option<Vec<PathBuf>> {
+    let dirs: Vec<PathBuf> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| expand_home(Path::new(dir)))
+        .collect();
+
+    (!dirs.is_empty()).then_some(dirs)
+}
+
+/// Find the log roots: the `projects` folder of each `CLAUDE_CONFIG_DIR`
+/// entry, or of the default config directories, followed by `extra`
+/// directories which are searched as they are.
+pub fn discover_log_roots(extra: &[PathBuf]) -> LogRoots {
+    let config_dirs = env::var(CONFIG_DIR_ENV)
+        .ok()
+        .and_then(|value| parse_config_dirs(&value));
+    find_log_roots(config_dirs, dirs::home_dir(), extra)
+}
+
+fn find_log_roots(
+    config_dirs: Option<Vec<PathBuf>>,
+    home: Option<PathBuf>,
+    extra: &[PathBuf],
+) -> LogRoots {
+    let mut roots = LogRoots::default();
+    let mut seen = HashSet::new();
+
+    if let Some(config_dirs) = config_dirs {
+        for dir in config_dirs {
+            let path = dir.join("projects");
+            if !roots.add(&path, &mut seen) {
+                roots.missing.push(path);
+            }
+        }
+    } else if let Some(home) = home {
+        let defaults: Vec<PathBuf> = DEFAULT_CONFIG_DIRS
+            .iter()
+            .map(|dir| home.join(dir).join("projects"))
+            .collect();
+        let mut any_found = false;
+        for path in &defaults {
+            any_found |= roots.add(path, &mut seen);
+        }
+        if !any_found {
+            roots.missing.extend(defaults);
+        }
+    } else {
+        eprintln!("Could not find home directory; set {}", CONFIG_DIR_ENV);
+    }
+
+    for dir in extra {
+        let path = expand_home(dir);
+        if !roots.add(&path, &mut seen) {
+            roots.missing.push(path);
+        }
+    }
+
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(path: &Path) -> PathBuf {
+        fs::canonicalize(path).unwrap()
+    }
+
+    #[test]
+    fn parses_config_dir_lists() {
+        let cases: [(&str, Option<&[&str]>); 5] = [
+            ("", None),
+            (" , ,", None),
+            ("/a", Some(&["/a"])),
+            ("/a, ,/b,,", Some(&["/a", "/b"])),
+            (" /a , /a ", Some(&["/a", "/a"])),
+        ];
+
+        for (value, expected) in cases {
+            let expected = expected.map(|dirs| dirs.iter().map(PathBuf::from).collect());
+            assert_eq!(parse_config_dirs(value), expected, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn config_dirs_replace_the_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path().join("home");
+        let config = dir.path().join("config");
+        fs::create_dir_all(home.join(".claude/projects")).unwrap();
+        fs::create_dir_all(config.join("projects")).unwrap();
+        let missing = dir.path().join("missing");
+
+        let value = format!("{0}, ,{0},{1}", config.display(), missing.display());
+        let roots = find_log_roots(parse_config_dirs(&value), Some(home), &[]);
+
+        assert_eq!(roots.found, [canonical(&config.join("projects"))]);
+        assert_eq!(roots.missing, [missing.join("projects")]);
+    }
+
+    #[test]
+    fn defaults_are_missing_only_when_neither_exists() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path().to_path_buf();
+
+        let roots = find_log_roots(None, Some(home.clone()), &[]);
+        assert!(roots.found.is_empty());
+        assert_eq!(roots.missing.len(), 2);
+
+        fs::create_dir_all(home.join(".config/claude/projects")).unwrap();
+        let roots = find_log_roots(None, Some(home.clone()), &[]);
+        assert_eq!(
+            roots.found,
+            [canonical(&home.join(".config/claude/projects"))]
+        );
+        assert!(roots.missing.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn roots_reached_through_symlinks_are_listed_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path().join("home");
+        let real = home.join(".claude/projects");
+        fs::create_dir_all(&real).unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        let chained = dir.path().join("chained");
+        std::os::unix::fs::symlink(&link, &chained).unwrap();
+
+        let extra = [link, real.clone(), chained];
+        let roots = find_log_roots(None, Some(home), &extra);
+        assert_eq!(roots.found, [canonical(&real)]);
+        assert!(roots.missing.is_empty());
+    }
+}
@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,10 +12,35 @@ pub struct LogEntry {
     pub message: Message,
     #[serde(rename = "costUSD")]
     pub cost_usd: Option<f64>,
+    #[serde(rename = "requestId", default)]
+    pub request_id: Option<String>,
+    /// Encoded `projects/<dir>` name of the file this entry was read from.
+    /// Not part of Claude Code's log format; filled in by the parser.
+    #[serde(rename = "projectDir", default)]
+    pub project_dir: Option<String>,
+}
+
+impl LogEntry {
+    pub fn parsed_timestamp(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.timestamp)
+            .ok()
+            .map(|dt| dt.with_timezone(&Utc))
+    }
+
+    /// Key identifying a single API response. Claude Code writes the same
+    /// assistant message several times while streaming and again when a
+    /// session is resumed into a new file, so this is used to count it once.
+    /// Entries missing the message id or the request id have no key and are
+    /// always counted; older logs don't record request ids.
+    pub fn dedup_key(&self) -> Option<(String, String)> {
+        Some((self.message.id.clone()?, self.request_id.clone()?))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
+    #[serde(default)]
+    pub id: Option<String>,
     pub role: Option<String>,
     pub model: Option<String>,
     pub usage: Option<TokenUsage>,
@@ -28,6 +56,42 @@ pub struct TokenUsage {
     pub cache_creation_input_tokens: Option<u64>,
     #[serde(default)]
     pub cache_read_input_tokens: Option<u64>,
+    /// Cache writes split by TTL, only present in newer logs
+    #[serde(default)]
+    pub cache_creation: Option<CacheCreation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CacheCreation {
+    #[serde(default)]
+    pub ephemeral_5m_input_tokens: u64,
+    #[serde(default)]
+    pub ephemeral_1h_input_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens.unwrap_or(0)
+            + self.cache_read_input_tokens.unwrap_or(0)
+    }
+
+    /// Cache writes with the 5-minute TTL. Logs without the split only used
+    /// the 5-minute cache, so the flat count is attributed here.
+    pub fn cache_creation_5m_tokens(&self) -> u64 {
+        match &self.cache_creation {
+            Some(split) => split.ephemeral_5m_input_tokens,
+            None => self.cache_creation_input_tokens.unwrap_or(0),
+        }
+    }
+
+    /// Cache writes with the 1-hour TTL
+    pub fn cache_creation_1h_tokens(&self) -> u64 {
+        self.cache_creation
+            .as_ref()
+            .map_or(0, |split| split.ephemeral_1h_input_tokens)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -38,10 +102,25 @@ pub struct AggregatedTokens {
     pub output_tokens: u64,
     #[serde(rename = "cacheCreationInputTokens")]
     pub cache_creation_input_tokens: u64,
+    #[serde(rename = "cacheCreation5mInputTokens")]
+    pub cache_creation_5m_input_tokens: u64,
+    #[serde(rename = "cacheCreation1hInputTokens")]
+    pub cache_creation_1h_input_tokens: u64,
     #[serde(rename = "cacheReadInputTokens")]
     pub cache_read_input_tokens: u64,
 }
 
+impl AggregatedTokens {
+    pub fn add_usage(&mut self, usage: &TokenUsage) {
+        self.input_tokens += usage.input_tokens;
+        self.output_tokens += usage.output_tokens;
+        self.cache_creation_input_tokens += usage.cache_creation_input_tokens.unwrap_or(0);
+        self.cache_creation_5m_input_tokens += usage.cache_creation_5m_tokens();
+        self.cache_creation_1h_input_tokens += usage.cache_creation_1h_tokens();
+        self.cache_read_input_tokens += usage.cache_read_input_tokens.unwrap_or(0);
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelUsage {
     pub model: String,
@@ -51,12 +130,24 @@ pub struct ModelUsage {
     pub output_tokens: u64,
     #[serde(rename = "cacheCreationInputTokens")]
     pub cache_creation_input_tokens: u64,
+    #[serde(rename = "cacheCreation5mInputTokens")]
+    pub cache_creation_5m_input_tokens: u64,
+    #[serde(rename = "cacheCreation1hInputTokens")]
+    pub cache_creation_1h_input_tokens: u64,
     #[serde(rename = "cacheReadInputTokens")]
     pub cache_read_input_tokens: u64,
     #[serde(rename = "costUsd")]
     pub cost_usd: f64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnpricedModel {
+    pub model: String,
+    pub tokens: AggregatedTokens,
+    #[serde(rename = "estimatedCostUsd")]
+    pub estimated_cost_usd: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UsageData {
     #[serde(rename = "totalCostUsd")]
@@ -65,6 +156,9 @@ pub struct UsageData {
     pub total_tokens: AggregatedTokens,
     #[serde(rename = "modelBreakdown")]
     pub model_breakdown: Vec<ModelUsage>,
+    /// Models with no matching price; their share of the total is an estimate
+    #[serde(rename = "unpricedModels")]
+    pub unpriced_models: Vec<UnpricedModel>,
     #[serde(rename = "lastUpdated")]
     pub last_updated: String,
 }
@@ -84,6 +178,57 @@ pub struct BillingWindow {
     pub remaining_minutes: i64,
     #[serde(rename = "isActive")]
     pub is_active: bool,
+    /// Idle time between two blocks rather than a block itself
+    #[serde(rename = "isGap")]
+    pub is_gap: bool,
+    #[serde(rename = "lastEntryTime")]
+    pub last_entry_time: Option<String>,
+    /// Pace over the last few minutes, only for the active block
+    #[serde(rename = "burnRate")]
+    pub burn_rate: Option<BurnRate>,
+    /// Totals at block end if the burn rate holds, only for the active block
+    pub projection: Option<BlockProjection>,
+    /// Cap the block is measured against, from the plan or history
+    pub limit: Option<UsageLimit>,
+    #[serde(rename = "percentUsed")]
+    pub percent_used: Option<f64>,
+    /// When the active block will reach its cap at the current burn rate
+    #[serde(rename = "predictedLimitTime")]
+    pub predicted_limit_time: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitSource {
+    /// Set by the configured plan
+    Plan,
+    /// Highest block seen so far, used when no plan is configured
+    Historical,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageLimit {
+    #[serde(rename = "tokenLimit")]
+    pub token_limit: Option<u64>,
+    #[serde(rename = "costLimitUsd")]
+    pub cost_limit_usd: Option<f64>,
+    pub source: LimitSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BurnRate {
+    #[serde(rename = "tokensPerMinute")]
+    pub tokens_per_minute: f64,
+    #[serde(rename = "costPerHour")]
+    pub cost_per_hour: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockProjection {
+    #[serde(rename = "totalTokens")]
+    pub total_tokens: u64,
+    #[serde(rename = "costUsd")]
+    pub cost_usd: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -102,3 +247,166 @@ pub struct SessionSummary {
     pub total_cost_usd: f64,
     pub models: Vec<String>,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeriesGranularity {
+    Hour,
+    Day,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesBucket {
+    #[serde(rename = "startTime")]
+    pub start_time: String,
+    #[serde(rename = "endTime")]
+    pub end_time: String,
+    /// Bucket start as wall-clock time in the series' zone, e.g.
+    /// "2025-01-31" or "2025-01-31 14:00"
+    pub label: String,
+    #[serde(rename = "costUsd")]
+    pub cost_usd: f64,
+    pub tokens: AggregatedTokens,
+    #[serde(rename = "modelBreakdown")]
+    pub model_breakdown: Vec<ModelUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageSeries {
+    pub granularity: SeriesGranularity,
+    pub buckets: Vec<SeriesBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSummary {
+    #[serde(rename = "projectPath")]
+    pub project_path: String,
+    #[serde(rename = "totalCostUsd")]
+    pub total_cost_usd: f64,
+    pub tokens: AggregatedTokens,
+    #[serde(rename = "sessionCount")]
+    pub session_count: u32,
+    #[serde(rename = "messageCount")]
+    pub message_count: u32,
+    #[serde(rename = "lastActivity")]
+    pub last_activity: String,
+}
+
+/// Why a line of a log file didn't become an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SkipReason {
+    InvalidUtf8,
+    /// Not JSON at all, e.g. a line cut short by a crash
+    InvalidJson,
+    /// Has a message, but its fields don't fit `LogEntry`
+    InvalidEntry,
+    /// JSON without a message, such as a summary line
+    NotAnEntry,
+    /// A message without token usage, such as a user prompt
+    NoUsage,
+}
+
+impl SkipReason {
+    /// Whether lines skipped for this reason are broken rather than just
+    /// not usage records
+    pub fn is_malformed(self) -> bool {
+        matches!(
+            self,
+            SkipReason::InvalidUtf8 | SkipReason::InvalidJson | SkipReason::InvalidEntry
+        )
+    }
+}
+
+/// Malformed lines kept per file as examples
+pub const MAX_PARSE_SAMPLES: usize = 5;
+
+/// Longest part of a malformed line kept in a sample, in characters
+const SAMPLE_EXCERPT_CHARS: usize = 120;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParseSample {
+    /// 1-based line number in the file
+    pub line: u64,
+    pub reason: SkipReason,
+    pub error: String,
+    pub excerpt: String,
+}
+
+/// What parsing a log file kept and skipped
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParseStats {
+    #[serde(rename = "linesRead")]
+    pub lines_read: u64,
+    /// Entries with token usage, before copies across files are dropped
+    #[serde(rename = "entriesKept")]
+    pub entries_kept: u64,
+    pub skipped: BTreeMap<SkipReason, u64>,
+    /// The first few malformed lines
+    pub samples: Vec<ParseSample>,
+}
+
+impl ParseStats {
+    pub fn skip(&mut self, reason: SkipReason) {
+        *self.skipped.entry(reason).or_default() += 1;
+    }
+
+    /// Skip a line that failed to parse, keeping it as a sample if it is
+    /// malformed and there is room
+    pub fn reject(&mut self, reason: SkipReason, line: u64, error: String, text: &str) {
+        self.skip(reason);
+        if reason.is_malformed() && self.samples.len() < MAX_PARSE_SAMPLES {
+            self.samples.push(ParseSample {
+                line,
+                reason,
+                error,
+                excerpt: text.trim_end().chars().take(SAMPLE_EXCERPT_CHARS).collect(),
+            });
+        }
+    }
+
+    pub fn malformed_lines(&self) -> u64 {
+        self.skipped
+            .iter()
+            .filter(|(reason, _)| reason.is_malformed())
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Add the stats for lines read after these ones
+    pub fn extend(&mut self, later: ParseStats) {
+        let room = MAX_PARSE_SAMPLES.saturating_sub(self.samples.len());
+        self.samples.extend(
+            later
+                .samples
+                .into_iter()
+                .take(room)
+                .map(|sample| ParseSample {
+                    line: sample.line + self.lines_read,
+                    ..sample
+                }),
+        );
+        self.lines_read += later.lines_read;
+        self.entries_kept += later.entries_kept;
+        for (reason, count) in later.skipped {
+            *self.skipped.entry(reason).or_default() += count;
+        }
+    }
+}
+
+/// Parse results for one log file, or why it couldn't be read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiagnostics {
+    pub path: String,
+    pub error: Option<String>,
+    pub stats: ParseStats,
+}
+
+/// What `get_parse_diagnostics` reports: the configured log directories that
+/// don't exist, then every log file found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParseDiagnostics {
+    #[serde(rename = "missingRoots")]
+    pub missing_roots: Vec<String>,
+    pub files: Vec<FileDiagnostics>,
+}
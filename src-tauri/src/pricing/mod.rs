@@ -1,91 +1,817 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
 use crate::parser::types::TokenUsage;
 
+/// Directory under the platform config dir holding user overrides
+pub const CONFIG_DIR_NAME: &str = "claude-usage";
+
+const PRICING_FILE_NAMES: [&str; 2] = ["pricing.toml", "pricing.json"];
+
+/// Higher rates applied to a whole request once its input grows past a
+/// threshold, as with Sonnet's long-context pricing above 200K tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PricingTier {
+    /// Applies when input + cache creation + cache read tokens exceed this
+    pub above_input_tokens: u64,
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+    pub cache_creation_per_million: f64,
+    /// Defaults to twice the tier's input rate
+    #[serde(default)]
+    pub cache_creation_1h_per_million: Option<f64>,
+    pub cache_read_per_million: f64,
+}
+
+/// Rates for one model over one period. A model with several price changes
+/// is described by several rules sharing a pattern with disjoint periods.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ModelPricing {
     pub input_per_million: f64,
     pub output_per_million: f64,
+    /// 5-minute cache writes
     pub cache_creation_per_million: f64,
+    /// 1-hour cache writes. Defaults to twice the input rate, matching
+    /// Anthropic's multiplier for the longer TTL.
+    #[serde(default)]
+    pub cache_creation_1h_per_million: Option<f64>,
     pub cache_read_per_million: f64,
+    /// First UTC day these rates apply (inclusive), open-ended if unset
+    #[serde(default)]
+    pub effective_from: Option<NaiveDate>,
+    /// Day these rates stop applying (exclusive), open-ended if unset
+    #[serde(default)]
+    pub effective_to: Option<NaiveDate>,
+    /// Long-context tiers, sorted by ascending threshold
+    #[serde(default)]
+    pub tiers: Vec<PricingTier>,
 }
 
-pub fn get_pricing(model: &str) -> ModelPricing {
-    let model_lower = model.to_lowercase();
+/// Per-million rates after picking a tier for a request
+struct TokenRates {
+    input: f64,
+    output: f64,
+    cache_creation: f64,
+    cache_creation_1h: f64,
+    cache_read: f64,
+}
 
-    if model_lower.contains("opus-4-5") || model_lower.contains("opus-4.5") {
-        ModelPricing {
-            input_per_million: 5.00,
-            output_per_million: 25.00,
-            cache_creation_per_million: 6.25,
-            cache_read_per_million: 0.50,
-        }
-    } else if model_lower.contains("sonnet-4-5")
-        || model_lower.contains("sonnet-4.5")
-        || model_lower.contains("3-5-sonnet")
-        || model_lower.contains("3.5-sonnet")
-    {
-        ModelPricing {
-            input_per_million: 3.00,
-            output_per_million: 15.00,
-            cache_creation_per_million: 3.75,
-            cache_read_per_million: 0.30,
+impl ModelPricing {
+    pub fn is_effective_on(&self, date: NaiveDate) -> bool {
+        self.effective_from.is_none_or(|from| date >= from)
+            && self.effective_to.is_none_or(|to| date < to)
+    }
+
+    /// Rates for a request whose total input is `input_tokens`
+    fn rates_for(&self, input_tokens: u64) -> TokenRates {
+        match self
+            .tiers
+            .iter()
+            .rev()
+            .find(|tier| input_tokens > tier.above_input_tokens)
+        {
+            Some(tier) => TokenRates {
+                input: tier.input_per_million,
+                output: tier.output_per_million,
+                cache_creation: tier.cache_creation_per_million,
+                cache_creation_1h: tier
+                    .cache_creation_1h_per_million
+                    .unwrap_or(tier.input_per_million * 2.0),
+                cache_read: tier.cache_read_per_million,
+            },
+            None => TokenRates {
+                input: self.input_per_million,
+                output: self.output_per_million,
+                cache_creation: self.cache_creation_per_million,
+                cache_creation_1h: self
+                    .cache_creation_1h_per_million
+                    .unwrap_or(self.input_per_million * 2.0),
+                cache_read: self.cache_read_per_million,
+            },
         }
-    } else if model_lower.contains("sonnet-4-") || model_lower.contains("sonnet-4.") {
-        ModelPricing {
-            input_per_million: 3.00,
-            output_per_million: 15.00,
-            cache_creation_per_million: 3.75,
-            cache_read_per_million: 0.30,
+    }
+}
+
+const OPUS_4_5: ModelPricing = ModelPricing {
+    input_per_million: 5.00,
+    output_per_million: 25.00,
+    cache_creation_per_million: 6.25,
+    cache_creation_1h_per_million: None,
+    cache_read_per_million: 0.50,
+    effective_from: None,
+    effective_to: None,
+    tiers: Vec::new(),
+};
+
+const OPUS: ModelPricing = ModelPricing {
+    input_per_million: 15.00,
+    output_per_million: 75.00,
+    cache_creation_per_million: 18.75,
+    cache_creation_1h_per_million: None,
+    cache_read_per_million: 1.50,
+    effective_from: None,
+    effective_to: None,
+    tiers: Vec::new(),
+};
+
+const SONNET: ModelPricing = ModelPricing {
+    input_per_million: 3.00,
+    output_per_million: 15.00,
+    cache_creation_per_million: 3.75,
+    cache_creation_1h_per_million: None,
+    cache_read_per_million: 0.30,
+    effective_from: None,
+    effective_to: None,
+    tiers: Vec::new(),
+};
+
+const HAIKU_4_5: ModelPricing = ModelPricing {
+    input_per_million: 1.00,
+    output_per_million: 5.00,
+    cache_creation_per_million: 1.25,
+    cache_creation_1h_per_million: None,
+    cache_read_per_million: 0.10,
+    effective_from: None,
+    effective_to: None,
+    tiers: Vec::new(),
+};
+
+/// After the December 2024 price cut
+const HAIKU_3_5: ModelPricing = ModelPricing {
+    input_per_million: 0.80,
+    output_per_million: 4.00,
+    cache_creation_per_million: 1.00,
+    cache_creation_1h_per_million: None,
+    cache_read_per_million: 0.08,
+    effective_from: None,
+    effective_to: None,
+    tiers: Vec::new(),
+};
+
+const HAIKU_3: ModelPricing = ModelPricing {
+    input_per_million: 0.25,
+    output_per_million: 1.25,
+    cache_creation_per_million: 0.30,
+    cache_creation_1h_per_million: None,
+    cache_read_per_million: 0.03,
+    effective_from: None,
+    effective_to: None,
+    tiers: Vec::new(),
+};
+
+/// How a pricing rule's pattern is compared against a model name
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchKind {
+    Exact,
+    Prefix,
+    #[default]
+    Glob,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricingRule {
+    #[serde(rename = "match", default)]
+    pub kind: MatchKind,
+    pub pattern: String,
+    #[serde(flatten)]
+    pub pricing: ModelPricing,
+}
+
+impl PricingRule {
+    fn new(kind: MatchKind, pattern: &str, pricing: ModelPricing) -> Self {
+        Self {
+            kind,
+            pattern: pattern.to_lowercase(),
+            pricing,
         }
-    } else if model_lower.contains("haiku-4-5")
-        || model_lower.contains("haiku-4.5")
-        || model_lower.contains("3-5-haiku")
-        || model_lower.contains("3.5-haiku")
-    {
-        ModelPricing {
-            input_per_million: 1.00,
-            output_per_million: 5.00,
-            cache_creation_per_million: 1.25,
-            cache_read_per_million: 0.10,
+    }
+
+    /// `model` must already be lowercased
+    fn matches(&self, model: &str, date: NaiveDate) -> bool {
+        let name_matches = match self.kind {
+            MatchKind::Exact => model == self.pattern,
+            MatchKind::Prefix => model.starts_with(&self.pattern),
+            MatchKind::Glob => glob_match(&self.pattern, model),
+        };
+        name_matches && self.pricing.is_effective_on(date)
+    }
+}
+
+/// Match `text` against a pattern where `*` is any run of characters and
+/// `?` is any single character
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
         }
-    } else if model_lower.contains("haiku") {
-        ModelPricing {
-            input_per_million: 0.25,
-            output_per_million: 1.25,
-            cache_creation_per_million: 0.30,
-            cache_read_per_million: 0.03,
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn default_rules() -> &'static [PricingRule] {
+    static DEFAULT_RULES: OnceLock<Vec<PricingRule>> = OnceLock::new();
+    DEFAULT_RULES.get_or_init(|| {
+        // Haiku 3.5 launched at Haiku 4.5's rates and was cut on this day
+        let haiku_3_5_cut = NaiveDate::from_ymd_opt(2024, 12, 3);
+        // The 1M context window, and with it long-context pricing, arrived
+        // on this day. Earlier Sonnet 4 requests fall through to `*sonnet*`.
+        let long_context = NaiveDate::from_ymd_opt(2025, 8, 12);
+
+        let sonnet_4 = ModelPricing {
+            effective_from: long_context,
+            tiers: vec![PricingTier {
+                above_input_tokens: 200_000,
+                input_per_million: 6.00,
+                output_per_million: 22.50,
+                cache_creation_per_million: 7.50,
+                cache_creation_1h_per_million: None,
+                cache_read_per_million: 0.60,
+            }],
+            ..SONNET
+        };
+
+        let table: [(&[&str], ModelPricing); 8] = [
+            (&["*opus-4-5*", "*opus-4.5*"], OPUS_4_5),
+            (&["*sonnet-4*"], sonnet_4),
+            (&["*sonnet*"], SONNET),
+            (&["*haiku-4-5*", "*haiku-4.5*"], HAIKU_4_5),
+            (
+                &["*3-5-haiku*", "*3.5-haiku*"],
+                ModelPricing {
+                    effective_to: haiku_3_5_cut,
+                    ..HAIKU_4_5
+                },
+            ),
+            (
+                &["*3-5-haiku*", "*3.5-haiku*"],
+                ModelPricing {
+                    effective_from: haiku_3_5_cut,
+                    ..HAIKU_3_5
+                },
+            ),
+            (&["*haiku*"], HAIKU_3),
+            (&["*opus*"], OPUS),
+        ];
+
+        table
+            .iter()
+            .flat_map(|(patterns, pricing)| {
+                patterns
+                    .iter()
+                    .map(|p| PricingRule::new(MatchKind::Glob, p, pricing.clone()))
+            })
+            .collect()
+    })
+}
+
+/// Rules loaded from the user's pricing file, checked before the defaults
+static USER_RULES: RwLock<Vec<PricingRule>> = RwLock::new(Vec::new());
+
+#[derive(Debug, Error)]
+pub enum PricingError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid TOML in {path}: {source}")]
+    Toml {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("invalid JSON in {path}: {source}")]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("{path}: model entry {index} ({pattern:?}): {reason}")]
+    InvalidRule {
+        path: PathBuf,
+        index: usize,
+        pattern: String,
+        reason: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct PricingFile {
+    #[serde(default, rename = "model")]
+    models: Vec<PricingRule>,
+}
+
+fn validate_rates(rates: [(&str, f64); 5]) -> Result<(), String> {
+    for (name, rate) in rates {
+        if !rate.is_finite() || rate < 0.0 {
+            return Err(format!("{} must be a non-negative number, got {}", name, rate));
         }
-    } else if model_lower.contains("opus") {
-        ModelPricing {
-            input_per_million: 15.00,
-            output_per_million: 75.00,
-            cache_creation_per_million: 18.75,
-            cache_read_per_million: 1.50,
+    }
+    Ok(())
+}
+
+fn validate_rule(rule: &PricingRule) -> Result<(), String> {
+    if rule.pattern.trim().is_empty() {
+        return Err("pattern must not be empty".to_string());
+    }
+
+    validate_rates([
+        ("input_per_million", rule.pricing.input_per_million),
+        ("output_per_million", rule.pricing.output_per_million),
+        ("cache_creation_per_million", rule.pricing.cache_creation_per_million),
+        (
+            "cache_creation_1h_per_million",
+            rule.pricing.cache_creation_1h_per_million.unwrap_or(0.0),
+        ),
+        ("cache_read_per_million", rule.pricing.cache_read_per_million),
+    ])?;
+
+    for (i, tier) in rule.pricing.tiers.iter().enumerate() {
+        if tier.above_input_tokens == 0 {
+            return Err(format!("tier {}: above_input_tokens must be positive", i));
         }
-    } else if model_lower.contains("sonnet") {
-        ModelPricing {
-            input_per_million: 3.00,
-            output_per_million: 15.00,
-            cache_creation_per_million: 3.75,
-            cache_read_per_million: 0.30,
+        validate_rates([
+            ("input_per_million", tier.input_per_million),
+            ("output_per_million", tier.output_per_million),
+            (
+                "cache_creation_per_million",
+                tier.cache_creation_per_million,
+            ),
+            (
+                "cache_creation_1h_per_million",
+                tier.cache_creation_1h_per_million.unwrap_or(0.0),
+            ),
+            ("cache_read_per_million", tier.cache_read_per_million),
+        ])
+        .map_err(|reason| format!("tier {}: {}", i, reason))?;
+    }
+
+    if let (Some(from), Some(to)) = (rule.pricing.effective_from, rule.pricing.effective_to) {
+        if from >= to {
+            return Err(format!(
+                "effective_from ({}) must be before effective_to ({})",
+                from, to
+            ));
         }
+    }
+
+    Ok(())
+}
+
+/// Parse and validate a pricing file. The format is picked by extension.
+pub fn load_pricing_file(path: &Path) -> Result<Vec<PricingRule>, PricingError> {
+    let content = fs::read_to_string(path).map_err(|source| PricingError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let file: PricingFile = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&content).map_err(|source| PricingError::Json {
+            path: path.to_path_buf(),
+            source,
+        })?
     } else {
-        ModelPricing {
-            input_per_million: 3.00,
-            output_per_million: 15.00,
-            cache_creation_per_million: 3.75,
-            cache_read_per_million: 0.30,
+        toml::from_str(&content).map_err(|source| PricingError::Toml {
+            path: path.to_path_buf(),
+            source,
+        })?
+    };
+
+    file.models
+        .into_iter()
+        .enumerate()
+        .map(|(index, rule)| {
+            validate_rule(&rule).map_err(|reason| PricingError::InvalidRule {
+                path: path.to_path_buf(),
+                index,
+                pattern: rule.pattern.clone(),
+                reason,
+            })?;
+            let mut pricing = rule.pricing;
+            pricing.tiers.sort_by_key(|tier| tier.above_input_tokens);
+            Ok(PricingRule::new(rule.kind, &rule.pattern, pricing))
+        })
+        .collect()
+}
+
+/// Location of the user's pricing file, whether or not it exists yet.
+/// `pricing.toml` wins over `pricing.json` when both are present.
+pub fn user_pricing_path() -> Option<PathBuf> {
+    let dir = dirs::config_dir()?.join(CONFIG_DIR_NAME);
+    let existing = PRICING_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists());
+    Some(existing.unwrap_or_else(|| dir.join(PRICING_FILE_NAMES[0])))
+}
+
+/// Whether a changed path is one of the files `reload_user_pricing` reads
+pub fn is_pricing_file(path: &Path) -> bool {
+    let Some(dir) = dirs::config_dir().map(|d| d.join(CONFIG_DIR_NAME)) else {
+        return false;
+    };
+    PRICING_FILE_NAMES.iter().any(|name| path == dir.join(name))
+}
+
+/// Load the user's pricing file over the built-in defaults. A missing file
+/// clears any overrides; an invalid one leaves the current rules in place.
+pub fn reload_user_pricing() -> Result<(), PricingError> {
+    let rules = match user_pricing_path() {
+        Some(path) if path.exists() => load_pricing_file(&path)?,
+        _ => Vec::new(),
+    };
+
+    *USER_RULES.write().unwrap() = rules;
+    Ok(())
+}
+
+/// Outcome of looking up a model's rates
+#[derive(Debug, Clone, PartialEq)]
+pub enum PricingLookup {
+    /// A user or built-in rule matched the model
+    Known(ModelPricing),
+    /// Nothing matched, so the model is priced at Sonnet rates as a guess
+    Fallback(ModelPricing),
+}
+
+impl PricingLookup {
+    pub fn pricing(&self) -> &ModelPricing {
+        match self {
+            PricingLookup::Known(pricing) | PricingLookup::Fallback(pricing) => pricing,
         }
     }
+
+    pub fn is_known(&self) -> bool {
+        matches!(self, PricingLookup::Known(_))
+    }
+}
+
+/// The first of `user_rules`, then the defaults, matching `model` on the
+/// given UTC day. `model` must already be lowercased.
+fn find_rule<'a>(
+    user_rules: &'a [PricingRule],
+    model: &str,
+    date: NaiveDate,
+) -> Option<&'a PricingRule> {
+    user_rules
+        .iter()
+        .chain(default_rules())
+        .find(|rule| rule.matches(model, date))
+}
+
+/// Run `f` on the rates in force for `model` on the given UTC day, along
+/// with whether they came from a matching rule. Avoids cloning the rule.
+fn with_pricing<R>(model: &str, date: NaiveDate, f: impl FnOnce(&ModelPricing, bool) -> R) -> R {
+    let model_lower = model.to_lowercase();
+
+    let user_rules = USER_RULES.read().unwrap();
+    match find_rule(&user_rules, &model_lower, date) {
+        Some(rule) => f(&rule.pricing, true),
+        None => f(&SONNET, false),
+    }
+}
+
+/// Rates in force for `model` on the given UTC day
+pub fn get_pricing(model: &str, date: NaiveDate) -> PricingLookup {
+    with_pricing(model, date, |pricing, is_known| {
+        if is_known {
+            PricingLookup::Known(pricing.clone())
+        } else {
+            PricingLookup::Fallback(pricing.clone())
+        }
+    })
+}
+
+/// Cost of a single request and whether it came from a known price
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestCost {
+    pub cost_usd: f64,
+    pub is_known: bool,
+}
+
+/// Price one request at the rates in force at `timestamp` so past usage
+/// keeps its cost when prices change. Unparseable timestamps use today's
+/// rates. Long-context tiers are chosen from this request's own input.
+pub fn price_request(model: &str, usage: &TokenUsage, timestamp: &str) -> RequestCost {
+    let date = DateTime::parse_from_rfc3339(timestamp)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+        .date_naive();
+
+    let cache_creation_5m_tokens = usage.cache_creation_5m_tokens();
+    let cache_creation_1h_tokens = usage.cache_creation_1h_tokens();
+    let cache_read_tokens = usage.cache_read_input_tokens.unwrap_or(0);
+    let total_input = usage.input_tokens
+        + cache_creation_5m_tokens
+        + cache_creation_1h_tokens
+        + cache_read_tokens;
+
+    with_pricing(model, date, |pricing, is_known| {
+        let rates = pricing.rates_for(total_input);
+
+        let input_cost = (usage.input_tokens as f64 / 1_000_000.0) * rates.input;
+        let output_cost = (usage.output_tokens as f64 / 1_000_000.0) * rates.output;
+        let cache_creation_cost = (cache_creation_5m_tokens as f64 / 1_000_000.0)
+            * rates.cache_creation
+            + (cache_creation_1h_tokens as f64 / 1_000_000.0) * rates.cache_creation_1h;
+        let cache_read_cost = (cache_read_tokens as f64 / 1_000_000.0) * rates.cache_read;
+
+        RequestCost {
+            cost_usd: input_cost + output_cost + cache_creation_cost + cache_read_cost,
+            is_known,
+        }
+    })
+}
+
+/// Cost of one request, see `price_request`
+pub fn calculate_cost(model: &str, usage: &TokenUsage, timestamp: &str) -> f64 {
+    price_request(model, usage, timestamp).cost_usd
 }
 
-pub fn calculate_cost(model: &str, usage: &TokenUsage) -> f64 {
-    let pricing = get_pricing(model);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    fn write_pricing(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn glob_matches() {
+        let cases = [
+            ("*", "", true),
+            ("*", "claude-opus-4", true),
+            ("*opus*", "claude-opus-4", true),
+            ("*opus*", "claude-sonnet-4", false),
+            ("claude-*-4", "claude-opus-4", true),
+            ("claude-*-4", "claude-opus-4-1", false),
+            ("claude-?onnet*", "claude-sonnet-4", true),
+            ("claude-?onnet*", "claude-onnet-4", false),
+            ("*4-5*", "claude-haiku-4-5-20251001", true),
+            ("a*b*c", "aXbYbZc", true),
+            ("a*b*c", "aXbYc_", false),
+            ("exact", "exact", true),
+            ("exact", "exactly", false),
+        ];
+
+        for (pattern, text, expected) in cases {
+            assert_eq!(
+                glob_match(pattern, text),
+                expected,
+                "{:?} against {:?}",
+                pattern,
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn rules_match_by_kind() {
+        let date = day("2025-06-01");
+        let cases = [
+            (MatchKind::Exact, "claude-sonnet-4", "claude-sonnet-4", true),
+            (
+                MatchKind::Exact,
+                "claude-sonnet-4",
+                "claude-sonnet-4-5",
+                false,
+            ),
+            (MatchKind::Exact, "Claude-Sonnet-4", "claude-sonnet-4", true),
+            (
+                MatchKind::Prefix,
+                "claude-sonnet",
+                "claude-sonnet-4-5",
+                true,
+            ),
+            (MatchKind::Prefix, "sonnet", "claude-sonnet-4-5", false),
+            (MatchKind::Prefix, "claude-*", "claude-sonnet-4", false),
+            (MatchKind::Glob, "claude-*", "claude-sonnet-4", true),
+        ];
+
+        for (kind, pattern, model, expected) in cases {
+            let rule = PricingRule::new(kind, pattern, SONNET);
+            assert_eq!(
+                rule.matches(model, date),
+                expected,
+                "{:?} {:?} against {:?}",
+                kind,
+                pattern,
+                model
+            );
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_pricing(
+            dir.path(),
+            "pricing.toml",
+            r#"
+[[model]]
+match = "exact"
+pattern = "claude-sonnet-4-5"
+input_per_million = 1.0
+output_per_million = 1.0
+cache_creation_per_million = 1.0
+cache_read_per_million = 1.0
 
-    let input_cost = (usage.input_tokens as f64 / 1_000_000.0) * pricing.input_per_million;
-    let output_cost = (usage.output_tokens as f64 / 1_000_000.0) * pricing.output_per_million;
-    let cache_creation_cost = (usage.cache_creation_input_tokens.unwrap_or(0) as f64 / 1_000_000.0)
-        * pricing.cache_creation_per_million;
-    let cache_read_cost = (usage.cache_read_input_tokens.unwrap_or(0) as f64 / 1_000_000.0)
-        * pricing.cache_read_per_million;
+[[model]]
+match = "prefix"
+pattern = "claude-sonnet"
+input_per_million = 2.0
+output_per_million = 2.0
+cache_creation_per_million = 2.0
+cache_read_per_million = 2.0
+"#,
+        );
+        let user_rules = load_pricing_file(&path).unwrap();
+        let date = day("2025-06-01");
 
-    input_cost + output_cost + cache_creation_cost + cache_read_cost
+        // (model, expected input rate)
+        let cases = [
+            ("claude-sonnet-4-5", 1.0),
+            ("claude-sonnet-4", 2.0),
+            // No user rule matches, so the defaults decide, in their order
+            ("claude-opus-4-5-20251101", OPUS_4_5.input_per_million),
+            ("claude-opus-4-1", OPUS.input_per_million),
+            ("claude-3-5-haiku", HAIKU_3_5.input_per_million),
+            ("claude-3-haiku", HAIKU_3.input_per_million),
+        ];
+        for (model, expected) in cases {
+            let rule = find_rule(&user_rules, model, date).unwrap();
+            assert_eq!(rule.pricing.input_per_million, expected, "{}", model);
+        }
+        assert!(find_rule(&user_rules, "gpt-4", date).is_none());
+    }
+
+    #[test]
+    fn invalid_pricing_files_are_rejected() {
+        let rates = "input_per_million = 1.0\noutput_per_million = 1.0\ncache_creation_per_million = 1.0\ncache_read_per_million = 1.0\n";
+        let cases = [
+            (
+                "empty pattern",
+                "pricing.toml",
+                format!("[[model]]\npattern = \"  \"\n{}", rates),
+                "model entry 0 (\"  \"): pattern must not be empty",
+            ),
+            (
+                "negative rate",
+                "pricing.toml",
+                format!(
+                    "[[model]]\npattern = \"a*\"\n{}\n[[model]]\npattern = \"b*\"\n{}",
+                    rates,
+                    rates.replace("output_per_million = 1.0", "output_per_million = -3.0")
+                ),
+                "model entry 1 (\"b*\"): output_per_million must be a non-negative number, got -3",
+            ),
+            (
+                "reversed period",
+                "pricing.toml",
+                format!(
+                    "[[model]]\npattern = \"a*\"\neffective_from = \"2025-06-01\"\neffective_to = \"2025-01-01\"\n{}",
+                    rates
+                ),
+                "effective_from (2025-06-01) must be before effective_to (2025-01-01)",
+            ),
+            (
+                "malformed TOML",
+                "pricing.toml",
+                "[[model]\npattern = \"a*\"".to_string(),
+                "invalid TOML in",
+            ),
+            (
+                "missing rate",
+                "pricing.toml",
+                "[[model]]\npattern = \"a*\"\ninput_per_million = 1.0".to_string(),
+                "missing field `output_per_million`",
+            ),
+            (
+                "malformed JSON",
+                "pricing.json",
+                "{\"model\": [".to_string(),
+                "invalid JSON in",
+            ),
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        for (name, file_name, content, expected) in cases {
+            let path = write_pricing(dir.path(), file_name, &content);
+            let error = load_pricing_file(&path).unwrap_err().to_string();
+            assert!(
+                error.contains(expected) && error.contains(file_name),
+                "{}: {}",
+                name,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn rates_apply_within_their_period() {
+        let period = |from: Option<&str>, to: Option<&str>| ModelPricing {
+            effective_from: from.map(day),
+            effective_to: to.map(day),
+            ..SONNET
+        };
+        let cases = [
+            (None, None, "2020-01-01", true),
+            (Some("2025-06-01"), None, "2025-05-31", false),
+            (Some("2025-06-01"), None, "2025-06-01", true),
+            (None, Some("2025-06-01"), "2025-05-31", true),
+            (None, Some("2025-06-01"), "2025-06-01", false),
+            (Some("2025-06-01"), Some("2025-07-01"), "2025-06-30", true),
+            (Some("2025-06-01"), Some("2025-07-01"), "2025-07-01", false),
+        ];
+
+        for (from, to, date, expected) in cases {
+            assert_eq!(
+                period(from, to).is_effective_on(day(date)),
+                expected,
+                "{:?}..{:?} on {}",
+                from,
+                to,
+                date
+            );
+        }
+    }
+
+    #[test]
+    fn picks_the_period_in_force_on_each_side_of_a_boundary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_pricing(
+            dir.path(),
+            "pricing.toml",
+            r#"
+[[model]]
+pattern = "custom-model"
+effective_to = "2025-03-01"
+input_per_million = 1.0
+output_per_million = 1.0
+cache_creation_per_million = 1.0
+cache_read_per_million = 1.0
+
+[[model]]
+pattern = "custom-model"
+effective_from = "2025-03-01"
+input_per_million = 2.0
+output_per_million = 2.0
+cache_creation_per_million = 2.0
+cache_read_per_million = 2.0
+"#,
+        );
+        let user_rules = load_pricing_file(&path).unwrap();
+
+        // (model, date, expected input rate, has long-context tiers)
+        let cases = [
+            ("custom-model", "2025-02-28", 1.0, false),
+            ("custom-model", "2025-03-01", 2.0, false),
+            ("claude-3-5-haiku-20241022", "2024-12-02", 1.00, false),
+            ("claude-3-5-haiku-20241022", "2024-12-03", 0.80, false),
+            ("claude-sonnet-4-20250514", "2025-08-11", 3.00, false),
+            ("claude-sonnet-4-20250514", "2025-08-12", 3.00, true),
+            ("claude-opus-4-1-20250805", "2025-12-01", 15.00, false),
+            ("claude-opus-4-5-20251101", "2025-12-01", 5.00, false),
+        ];
+        for (model, date, input, tiered) in cases {
+            let rule = find_rule(&user_rules, model, day(date)).unwrap();
+            assert_eq!(
+                (
+                    rule.pricing.input_per_million,
+                    !rule.pricing.tiers.is_empty()
+                ),
+                (input, tiered),
+                "{} on {}",
+                model,
+                date
+            );
+        }
+    }
 }
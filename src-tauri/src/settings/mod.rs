@@ -0,0 +1,254 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use thiserror::Error;
+
+use crate::parser::blocks::Plan;
+use crate::parser::roots::{discover_log_roots, LogRoots};
+use crate::pricing::CONFIG_DIR_NAME;
+
+const SETTINGS_FILE_NAME: &str = "settings.json";
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("failed to read {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("failed to write {path}: {source}")]
+    Write { path: PathBuf, source: io::Error },
+    #[error("invalid JSON in {path}: {source}")]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("invalid settings: {0}")]
+    Invalid(String),
+}
+
+/// Percentages of a budget that raise an alert unless configured
+const DEFAULT_ALERT_THRESHOLDS: [u32; 3] = [50, 80, 100];
+
+/// Preferences changed from the dashboard and kept next to the pricing file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Settings {
+    /// Without a plan, blocks are measured against the busiest past block
+    #[serde(default)]
+    pub plan: Option<Plan>,
+    #[serde(default)]
+    pub budgets: Budgets,
+    #[serde(rename = "trayTitle", default)]
+    pub tray_title: TrayTitle,
+    #[serde(rename = "iconThresholds", default)]
+    pub icon_thresholds: IconThresholds,
+    /// More directories searched for logs, e.g. on a mounted volume
+    #[serde(rename = "extraLogDirs", default)]
+    pub extra_log_dirs: Vec<PathBuf>,
+    #[serde(rename = "watchMode", default)]
+    pub watch_mode: WatchMode,
+}
+
+/// How the log directories are watched for changes
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WatchMode {
+    /// Filesystem events, falling back to polling if they fail or go missing
+    #[default]
+    Auto,
+    /// Stat the logs on a timer, for mounts that never deliver events
+    Poll,
+}
+
+/// Percent of the active block's cap or the daily budget, whichever is
+/// higher, at which the tray icon turns amber and then red
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IconThresholds {
+    #[serde(rename = "warningPercent")]
+    pub warning_percent: f64,
+    #[serde(rename = "criticalPercent")]
+    pub critical_percent: f64,
+}
+
+impl Default for IconThresholds {
+    fn default() -> Self {
+        Self {
+            warning_percent: 75.0,
+            critical_percent: 90.0,
+        }
+    }
+}
+
+/// Text shown next to the tray icon
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TrayTitle {
+    Off,
+    /// Today's cost, e.g. "$12.40"
+    #[default]
+    Cost,
+    /// Share of the active block's cap, e.g. "63%"
+    Percent,
+    /// Time until the active block ends, e.g. "2h14m left"
+    TimeLeft,
+}
+
+/// Spending limits per calendar day, week (from Monday) and month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Budgets {
+    #[serde(rename = "dailyUsd", default)]
+    pub daily_usd: Option<f64>,
+    #[serde(rename = "weeklyUsd", default)]
+    pub weekly_usd: Option<f64>,
+    #[serde(rename = "monthlyUsd", default)]
+    pub monthly_usd: Option<f64>,
+    /// Percentages of each budget that trigger a notification
+    #[serde(default = "default_alert_thresholds")]
+    pub thresholds: Vec<u32>,
+}
+
+fn default_alert_thresholds() -> Vec<u32> {
+    DEFAULT_ALERT_THRESHOLDS.to_vec()
+}
+
+impl Default for Budgets {
+    fn default() -> Self {
+        Self {
+            daily_usd: None,
+            weekly_usd: None,
+            monthly_usd: None,
+            thresholds: default_alert_thresholds(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if let Some(ref plan) = self.plan {
+            plan.validate().map_err(SettingsError::Invalid)?;
+        }
+
+        let budgets = [
+            ("daily", self.budgets.daily_usd),
+            ("weekly", self.budgets.weekly_usd),
+            ("monthly", self.budgets.monthly_usd),
+        ];
+        for (name, budget) in budgets {
+            if budget.is_some_and(|b| !b.is_finite() || b <= 0.0) {
+                return Err(SettingsError::Invalid(format!(
+                    "{} budget must be a positive amount",
+                    name
+                )));
+            }
+        }
+        let icon = &self.icon_thresholds;
+        if !(icon.warning_percent.is_finite() && icon.critical_percent.is_finite())
+            || icon.warning_percent <= 0.0
+            || icon.warning_percent > icon.critical_percent
+        {
+            return Err(SettingsError::Invalid(
+                "icon thresholds must be positive with warning at or below critical".to_string(),
+            ));
+        }
+
+        if self.budgets.thresholds.contains(&0) {
+            return Err(SettingsError::Invalid(
+                "alert thresholds must be positive percentages".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Log directories from `CLAUDE_CONFIG_DIR` or the defaults, plus
+    /// `extra_log_dirs`
+    pub fn log_roots(&self) -> LogRoots {
+        discover_log_roots(&self.extra_log_dirs)
+    }
+}
+
+pub fn settings_path() -> Option<PathBuf> {
+    Some(
+        dirs::config_dir()?
+            .join(CONFIG_DIR_NAME)
+            .join(SETTINGS_FILE_NAME),
+    )
+}
+
+fn load_settings_file(path: &Path) -> Result<Settings, SettingsError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Settings::default()),
+        Err(source) => {
+            return Err(SettingsError::Read {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+
+    let settings: Settings =
+        serde_json::from_str(&content).map_err(|source| SettingsError::Json {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    settings.validate()?;
+    Ok(settings)
+}
+
+fn write_settings_file(path: &Path, settings: &Settings) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Write to a temporary file first so a crash never leaves a torn file
+    let tmp_path = path.with_extension("tmp");
+    let json = serde_json::to_string_pretty(settings)?;
+    fs::write(&tmp_path, json)?;
+    fs::rename(&tmp_path, path)
+}
+
+pub struct SettingsStore {
+    settings: RwLock<Settings>,
+    path: Option<PathBuf>,
+}
+
+impl SettingsStore {
+    /// Load the settings file, falling back to defaults if it is missing or
+    /// invalid
+    pub fn load() -> Self {
+        let path = settings_path();
+        let settings = match path.as_deref().map(load_settings_file) {
+            Some(Ok(settings)) => settings,
+            Some(Err(e)) => {
+                eprintln!("Ignoring settings file: {}", e);
+                Settings::default()
+            }
+            None => Settings::default(),
+        };
+
+        Self {
+            settings: RwLock::new(settings),
+            path,
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Validate and persist new settings. Nothing changes if either fails.
+    pub fn update(&self, settings: Settings) -> Result<(), SettingsError> {
+        settings.validate()?;
+
+        if let Some(ref path) = self.path {
+            write_settings_file(path, &settings).map_err(|source| SettingsError::Write {
+                path: path.clone(),
+                source,
+            })?;
+        }
+
+        *self.settings.write().unwrap() = settings;
+        Ok(())
+    }
+}
@@ -0,0 +1,391 @@
+use image::RgbaImage;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{
+    image::Image,
+    menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem},
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    App, AppHandle, Emitter, Manager, Wry,
+};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_opener::OpenerExt;
+use tauri_plugin_positioner::{Position, WindowExt};
+
+use crate::cache::EntryCache;
+use crate::parser::aggregate_usage;
+use crate::parser::blocks::billing_windows_for_range;
+use crate::parser::range::{ReportTimeZone, TimeRange};
+use crate::parser::types::{BillingWindow, UsageData};
+use crate::settings::{IconThresholds, Settings, SettingsStore, TrayTitle};
+use crate::watcher::emit_files_changed;
+
+pub const TRAY_ID: &str = "main";
+
+const MENU_REFRESH: &str = "refresh";
+const MENU_OPEN_LOGS: &str = "open-logs";
+const MENU_COPY_SUMMARY: &str = "copy-summary";
+const MENU_SETTINGS: &str = "settings";
+const MENU_QUIT: &str = "quit";
+
+/// How often the tray is redrawn so the countdown moves between log changes
+const REFRESH_INTERVAL_SECS: u64 = 60;
+
+/// Colours for the warning and critical icons (Tailwind amber-500, red-500)
+const WARNING_TINT: [u8; 3] = [0xf5, 0x9e, 0x0b];
+const CRITICAL_TINT: [u8; 3] = [0xef, 0x44, 0x44];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IconLevel {
+    Normal,
+    Warning,
+    Critical,
+}
+
+static ICONS: OnceLock<[Image<'static>; 3]> = OnceLock::new();
+
+/// Level the tray icon currently shows, so it is only swapped on change
+static SHOWN_LEVEL: Mutex<Option<IconLevel>> = Mutex::new(None);
+
+fn base_icon() -> RgbaImage {
+    let png_data = include_bytes!("../../icons/icon.png");
+    let img = image::load_from_memory(png_data).expect("failed to load icon");
+    img.to_rgba8()
+}
+
+fn to_image(rgba: RgbaImage) -> Image<'static> {
+    let (width, height) = rgba.dimensions();
+    Image::new_owned(rgba.into_raw(), width, height)
+}
+
+fn load_icon() -> Image<'static> {
+    to_image(base_icon())
+}
+
+/// The black template glyph recoloured, keeping its alpha
+fn tinted_icon(tint: [u8; 3]) -> Image<'static> {
+    let mut rgba = base_icon();
+    for pixel in rgba.pixels_mut() {
+        pixel.0[..3].copy_from_slice(&tint);
+    }
+    to_image(rgba)
+}
+
+fn icon_for(level: IconLevel) -> Image<'static> {
+    let icons = ICONS.get_or_init(|| {
+        [
+            load_icon(),
+            tinted_icon(WARNING_TINT),
+            tinted_icon(CRITICAL_TINT),
+        ]
+    });
+    icons[level as usize].clone()
+}
+
+fn icon_level(percent: Option<f64>, thresholds: &IconThresholds) -> IconLevel {
+    match percent {
+        Some(p) if p >= thresholds.critical_percent => IconLevel::Critical,
+        Some(p) if p >= thresholds.warning_percent => IconLevel::Warning,
+        _ => IconLevel::Normal,
+    }
+}
+
+fn format_time_left(minutes: i64) -> String {
+    let (hours, mins) = (minutes / 60, minutes % 60);
+    if hours > 0 {
+        format!("{}h{:02}m left", hours, mins)
+    } else {
+        format!("{}m left", mins)
+    }
+}
+
+/// Read-only lines at the top of the tray menu, rewritten on every refresh
+struct TrayMenu {
+    today: MenuItem<Wry>,
+    block: MenuItem<Wry>,
+    top_model: MenuItem<Wry>,
+}
+
+/// Today's usage and the active block, which everything in the tray reads
+pub(crate) struct TodayUsage {
+    pub(crate) usage: UsageData,
+    pub(crate) active: Option<BillingWindow>,
+}
+
+pub(crate) fn today_usage(app: &AppHandle, settings: &Settings) -> TodayUsage {
+    let entries = app.state::<EntryCache>().get_all_entries();
+    let today = TimeRange::preset("today", ReportTimeZone::Local);
+    let usage = aggregate_usage(&today.filter(&entries));
+    let active = billing_windows_for_range(&entries, &today, settings.plan.as_ref())
+        .into_iter()
+        .find(|w| w.is_active);
+
+    TodayUsage { usage, active }
+}
+
+impl TodayUsage {
+    fn top_model(&self) -> Option<&str> {
+        self.usage
+            .model_breakdown
+            .iter()
+            .max_by(|a, b| a.cost_usd.total_cmp(&b.cost_usd))
+            .map(|m| m.model.as_str())
+    }
+
+    fn block_line(&self) -> String {
+        match &self.active {
+            Some(w) => match w.percent_used {
+                Some(percent) => format!(
+                    "Block: {}, {:.0}% used",
+                    format_time_left(w.remaining_minutes),
+                    percent
+                ),
+                None => format!("Block: {}", format_time_left(w.remaining_minutes)),
+            },
+            None => "No active block".to_string(),
+        }
+    }
+
+    /// Plain-text summary for pasting into a chat or note
+    fn summary(&self) -> String {
+        let mut lines = vec![format!(
+            "Claude usage today: ${:.2}",
+            self.usage.total_cost_usd
+        )];
+
+        let mut models: Vec<_> = self.usage.model_breakdown.iter().collect();
+        models.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+        for m in models {
+            lines.push(format!(
+                "  {}: ${:.2} ({} in, {} out, {} cache write, {} cache read)",
+                m.model,
+                m.cost_usd,
+                m.input_tokens,
+                m.output_tokens,
+                m.cache_creation_input_tokens,
+                m.cache_read_input_tokens
+            ));
+        }
+
+        lines.push(self.block_line());
+        lines.join("\n")
+    }
+}
+
+struct TrayStatus {
+    title: Option<String>,
+    level: IconLevel,
+}
+
+fn tray_status(today: &TodayUsage, settings: &Settings) -> TrayStatus {
+    let today_cost = today.usage.total_cost_usd;
+    let active = today.active.as_ref();
+
+    let title = match settings.tray_title {
+        TrayTitle::Off => None,
+        TrayTitle::Cost => Some(format!("${:.2}", today_cost)),
+        TrayTitle::Percent => active
+            .and_then(|w| w.percent_used)
+            .map(|percent| format!("{:.0}%", percent)),
+        TrayTitle::TimeLeft => active.map(|w| format_time_left(w.remaining_minutes)),
+    };
+
+    // Whichever of the block cap and the daily budget is closer to its limit
+    let block_percent = active.and_then(|w| w.percent_used);
+    let budget_percent = settings
+        .budgets
+        .daily_usd
+        .map(|budget| today_cost / budget * 100.0);
+    let percent = block_percent
+        .into_iter()
+        .chain(budget_percent)
+        .reduce(f64::max);
+
+    TrayStatus {
+        title,
+        level: icon_level(percent, &settings.icon_thresholds),
+    }
+}
+
+fn update_menu(app: &AppHandle, today: &TodayUsage) {
+    let Some(menu) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+
+    let top_model = today.top_model().unwrap_or("none");
+    let updated = menu
+        .today
+        .set_text(format!("Today: ${:.2}", today.usage.total_cost_usd))
+        .and_then(|_| menu.block.set_text(today.block_line()))
+        .and_then(|_| menu.top_model.set_text(format!("Top model: {}", top_model)));
+    if let Err(e) = updated {
+        eprintln!("Failed to update tray menu: {:?}", e);
+    }
+}
+
+/// Redraw the tray title, icon and menu stats from the current cache and
+/// settings
+pub fn refresh_tray(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+
+    let settings = app.state::<SettingsStore>().get();
+    let today = today_usage(app, &settings);
+    update_menu(app, &today);
+    let status = tray_status(&today, &settings);
+
+    if let Err(e) = tray.set_title(status.title) {
+        eprintln!("Failed to set tray title: {:?}", e);
+    }
+
+    let mut shown = SHOWN_LEVEL.lock().unwrap();
+    if *shown != Some(status.level) {
+        // Only the uncoloured icon is a template; macOS would drop the tint
+        let updated = tray
+            .set_icon(Some(icon_for(status.level)))
+            .and_then(|_| tray.set_icon_as_template(status.level == IconLevel::Normal));
+        match updated {
+            Ok(()) => *shown = Some(status.level),
+            Err(e) => eprintln!("Failed to set tray icon: {:?}", e),
+        }
+    }
+}
+
+pub fn start_refresh_timer(app_handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(REFRESH_INTERVAL_SECS));
+        refresh_tray(&app_handle);
+    });
+}
+
+fn show_dashboard(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("dashboard") {
+        let _ = window.move_window(Position::TrayBottomCenter);
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn toggle_dashboard(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("dashboard") {
+        if window.is_visible().unwrap_or(false) {
+            let _ = window.hide();
+        } else {
+            show_dashboard(app);
+        }
+    }
+}
+
+/// Rescan every log file in the background, picking up anything the
+/// watcher missed
+pub fn refresh_now(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let roots = app.state::<SettingsStore>().get().log_roots();
+        let delta = app.state::<EntryCache>().initialize(&roots.found);
+        refresh_tray(&app);
+        emit_files_changed(&app, delta);
+    });
+}
+
+fn open_logs_folder(app: &AppHandle) {
+    let roots = app.state::<SettingsStore>().get().log_roots();
+    let Some(path) = roots.found.into_iter().next() else {
+        eprintln!("No Claude project paths found to open");
+        return;
+    };
+
+    if let Err(e) = app.opener().open_path(path.to_string_lossy(), None::<&str>) {
+        eprintln!("Failed to open {:?}: {:?}", path, e);
+    }
+}
+
+fn copy_summary(app: &AppHandle) {
+    let settings = app.state::<SettingsStore>().get();
+    let summary = today_usage(app, &settings).summary();
+    if let Err(e) = app.clipboard().write_text(summary) {
+        eprintln!("Failed to copy summary: {:?}", e);
+    }
+}
+
+fn open_settings(app: &AppHandle) {
+    show_dashboard(app);
+    if let Some(window) = app.get_webview_window("dashboard") {
+        let _ = window.emit("open-settings", ());
+    }
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        MENU_REFRESH => refresh_now(app),
+        MENU_OPEN_LOGS => open_logs_folder(app),
+        MENU_COPY_SUMMARY => copy_summary(app),
+        MENU_SETTINGS => open_settings(app),
+        MENU_QUIT => app.exit(0),
+        _ => {}
+    }
+}
+
+/// Create the tray icon and its menu. The stats lines are filled in by the
+/// first `refresh_tray`.
+pub fn build_tray(app: &App) -> tauri::Result<()> {
+    let today = MenuItem::with_id(app, "today", "Today: –", false, None::<&str>)?;
+    let block = MenuItem::with_id(app, "block", "No active block", false, None::<&str>)?;
+    let top_model = MenuItem::with_id(app, "top-model", "Top model: none", false, None::<&str>)?;
+
+    let refresh_i = MenuItem::with_id(app, MENU_REFRESH, "Refresh Now", true, None::<&str>)?;
+    let logs_i = MenuItem::with_id(app, MENU_OPEN_LOGS, "Open Logs Folder", true, None::<&str>)?;
+    let copy_i = MenuItem::with_id(
+        app,
+        MENU_COPY_SUMMARY,
+        "Copy Today's Summary",
+        true,
+        None::<&str>,
+    )?;
+    let settings_i = MenuItem::with_id(app, MENU_SETTINGS, "Settings…", true, None::<&str>)?;
+    let quit_i = MenuItem::with_id(app, MENU_QUIT, "Quit Claude Usage", true, None::<&str>)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &today,
+            &block,
+            &top_model,
+            &PredefinedMenuItem::separator(app)?,
+            &refresh_i,
+            &logs_i,
+            &copy_i,
+            &settings_i,
+            &PredefinedMenuItem::separator(app)?,
+            &quit_i,
+        ],
+    )?;
+
+    app.manage(TrayMenu {
+        today,
+        block,
+        top_model,
+    });
+
+    TrayIconBuilder::with_id(TRAY_ID)
+        .icon(load_icon())
+        .icon_as_template(true)
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(on_menu_event)
+        .on_tray_icon_event(|tray, event| {
+            tauri_plugin_positioner::on_tray_event(tray.app_handle(), &event);
+
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                toggle_dashboard(tray.app_handle());
+            }
+        })
+        .build(app)?;
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::cache::EntryCache;
+use crate::pricing::user_pricing_path;
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).ok()?.modified().ok()
+}
+
+/// Finds log changes by comparing file stats, for mounts and sandboxes that
+/// never deliver filesystem events
+pub struct Poller {
+    /// Every directory under the roots, with its modified time when it was
+    /// last listed
+    dirs: HashMap<PathBuf, Option<SystemTime>>,
+    pricing_modified: Option<SystemTime>,
+}
+
+impl Default for Poller {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Poller {
+    pub fn new() -> Self {
+        Self {
+            dirs: HashMap::new(),
+            pricing_modified: user_pricing_path().and_then(|p| modified(&p)),
+        }
+    }
+
+    /// JSONL files changed, created or deleted since the last poll. Cached
+    /// files are stat'ed; directories are only listed when their modified
+    /// time moves, which is when files are added or removed in them.
+    pub fn changed_logs(&mut self, roots: &[PathBuf], cache: &EntryCache) -> Vec<PathBuf> {
+        let mut changed = cache.stale_paths();
+
+        let mut pending: Vec<PathBuf> = roots
+            .iter()
+            .filter(|root| !self.dirs.contains_key(*root))
+            .cloned()
+            .collect();
+        self.dirs.retain(|dir, listed| {
+            if !roots.iter().any(|root| dir.starts_with(root)) {
+                return false;
+            }
+            let current = modified(dir);
+            if current.is_some() && current != *listed {
+                pending.push(dir.clone());
+            }
+            current.is_some()
+        });
+
+        while let Some(dir) = pending.pop() {
+            // Stat before listing so a change made meanwhile is seen next time
+            self.dirs.insert(dir.clone(), modified(&dir));
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+
+            for entry in entries.filter_map(|e| e.ok()) {
+                let path = entry.path();
+                // Symlinked directories aren't followed, so a link loop
+                // can't make the listing endless
+                let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+                if is_dir {
+                    if !self.dirs.contains_key(&path) {
+                        pending.push(path);
+                    }
+                } else if path.extension().is_some_and(|ext| ext == "jsonl")
+                    && !cache.contains(&path)
+                {
+                    changed.push(path);
+                }
+            }
+        }
+
+        changed
+    }
+
+    /// Whether the pricing file was edited, created or removed since the
+    /// last call
+    pub fn pricing_changed(&mut self) -> bool {
+        let current = user_pricing_path().and_then(|p| modified(&p));
+        std::mem::replace(&mut self.pricing_modified, current) != current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    /// Poll once and load what changed, as the watcher does
+    fn poll(poller: &mut Poller, roots: &[PathBuf], cache: &EntryCache) -> Vec<PathBuf> {
+        let mut changed = poller.changed_logs(roots, cache);
+        changed.sort();
+        cache.invalidate_paths(&changed);
+        changed
+    }
+
+    #[test]
+    fn finds_new_and_deleted_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = [dir.path().to_path_buf()];
+        let project = dir.path().join("project");
+        fs::create_dir(&project).unwrap();
+        let first = project.join("a.jsonl");
+        fs::write(&first, "").unwrap();
+        fs::write(project.join("notes.txt"), "").unwrap();
+        let cache = EntryCache::new();
+        let mut poller = Poller::new();
+
+        assert_eq!(
+            poll(&mut poller, &roots, &cache),
+            std::slice::from_ref(&first)
+        );
+        assert!(poll(&mut poller, &roots, &cache).is_empty());
+
+        let second = project.join("b.jsonl");
+        fs::write(&second, "").unwrap();
+        assert_eq!(poll(&mut poller, &roots, &cache), [second]);
+
+        fs::remove_file(&first).unwrap();
+        assert_eq!(
+            poll(&mut poller, &roots, &cache),
+            std::slice::from_ref(&first)
+        );
+        assert!(!cache.contains(&first));
+        assert!(poll(&mut poller, &roots, &cache).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn directory_with_unchanged_mtime_is_not_listed() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = [dir.path().to_path_buf()];
+        let cache = EntryCache::new();
+        let mut poller = Poller::new();
+        poll(&mut poller, &roots, &cache);
+
+        let listed = modified(dir.path()).unwrap();
+        fs::write(dir.path().join("a.jsonl"), "").unwrap();
+        File::open(dir.path())
+            .unwrap()
+            .set_modified(listed)
+            .unwrap();
+        assert!(poll(&mut poller, &roots, &cache).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_file_is_retried_only_once_it_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = [dir.path().join("logs")];
+        fs::create_dir(&roots[0]).unwrap();
+        // A directory can be stat'ed but not read as a log
+        let target = dir.path().join("target");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("inside"), "").unwrap();
+        let link = roots[0].join("broken.jsonl");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        let cache = EntryCache::new();
+        let mut poller = Poller::new();
+
+        assert_eq!(
+            poll(&mut poller, &roots, &cache),
+            std::slice::from_ref(&link)
+        );
+        assert!(cache.diagnostics()[0].error.is_some());
+
+        // Listing the directory again doesn't retry it
+        let other = roots[0].join("other.jsonl");
+        fs::write(&other, "").unwrap();
+        assert_eq!(poll(&mut poller, &roots, &cache), [other]);
+
+        fs::write(target.join("more"), "").unwrap();
+        assert_eq!(poll(&mut poller, &roots, &cache), [link]);
+        assert!(poll(&mut poller, &roots, &cache).is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+use notify_debouncer_mini::notify::{self, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::cache::file_id;
+
+/// Identifies a root directory. A directory deleted and recreated can get
+/// the same inode back, so its creation time is compared too.
+pub type RootId = (Option<u64>, Option<SystemTime>);
+
+/// Roots with a recursive watch and the directory each was watched as, so a
+/// deleted and recreated root is noticed
+pub type WatchedRoots = HashMap<PathBuf, RootId>;
+
+fn root_id(path: &Path) -> Option<RootId> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((file_id(&metadata), metadata.created().ok()))
+}
+
+/// Bring the recursive watches in line with `roots`. Returns whether any
+/// watch was added or dropped.
+pub fn sync_roots(
+    watcher: &mut dyn Watcher,
+    watched: &mut WatchedRoots,
+    roots: &[PathBuf],
+) -> notify::Result<bool> {
+    let mut changed = false;
+
+    watched.retain(|path, id| {
+        let current = roots.contains(path).then(|| root_id(path)).flatten();
+        if current == Some(*id) {
+            return true;
+        }
+        // The old watch may already be gone with the directory
+        let _ = watcher.unwatch(path);
+        eprintln!("Stopped watching: {:?}", path);
+        changed = true;
+        false
+    });
+
+    for path in roots {
+        if watched.contains_key(path) {
+            continue;
+        }
+        let Some(id) = root_id(path) else {
+            continue;
+        };
+        watcher.watch(path, RecursiveMode::Recursive)?;
+        eprintln!("Watching: {:?}", path);
+        watched.insert(path.clone(), id);
+        changed = true;
+    }
+
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::{Config, EventHandler, WatcherKind};
+    use std::time::UNIX_EPOCH;
+
+    /// Records watch calls, failing those on `fail_on`
+    #[derive(Default)]
+    struct FakeWatcher {
+        calls: Vec<(&'static str, PathBuf)>,
+        fail_on: Option<PathBuf>,
+    }
+
+    impl Watcher for FakeWatcher {
+        fn new<F: EventHandler>(_handler: F, _config: Config) -> notify::Result<Self> {
+            Ok(Self::default())
+        }
+
+        fn watch(&mut self, path: &Path, _mode: RecursiveMode) -> notify::Result<()> {
+            if self.fail_on.as_deref() == Some(path) {
+                return Err(notify::Error::path_not_found());
+            }
+            self.calls.push(("watch", path.to_path_buf()));
+            Ok(())
+        }
+
+        fn unwatch(&mut self, path: &Path) -> notify::Result<()> {
+            self.calls.push(("unwatch", path.to_path_buf()));
+            Ok(())
+        }
+
+        fn kind() -> WatcherKind {
+            WatcherKind::NullWatcher
+        }
+    }
+
+    /// Sync and return whether anything changed and the calls it made
+    fn sync(
+        watcher: &mut FakeWatcher,
+        watched: &mut WatchedRoots,
+        roots: &[PathBuf],
+    ) -> (bool, Vec<(&'static str, PathBuf)>) {
+        let changed = sync_roots(watcher, watched, roots).unwrap();
+        (changed, std::mem::take(&mut watcher.calls))
+    }
+
+    #[test]
+    fn follows_roots_appearing_and_disappearing() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("projects");
+        let roots = [root.clone()];
+        let mut watcher = FakeWatcher::default();
+        let mut watched = WatchedRoots::new();
+
+        assert_eq!(sync(&mut watcher, &mut watched, &roots), (false, vec![]));
+
+        fs::create_dir(&root).unwrap();
+        assert_eq!(
+            sync(&mut watcher, &mut watched, &roots),
+            (true, vec![("watch", root.clone())])
+        );
+        assert_eq!(sync(&mut watcher, &mut watched, &roots), (false, vec![]));
+
+        fs::remove_dir(&root).unwrap();
+        assert_eq!(
+            sync(&mut watcher, &mut watched, &roots),
+            (true, vec![("unwatch", root.clone())])
+        );
+        assert!(watched.is_empty());
+    }
+
+    #[test]
+    fn rewatches_a_recreated_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("projects");
+        fs::create_dir(&root).unwrap();
+        let roots = [root.clone()];
+        let mut watcher = FakeWatcher::default();
+        let mut watched = WatchedRoots::new();
+        sync(&mut watcher, &mut watched, &roots);
+
+        // Same inode, but created at another time
+        let (inode, _) = watched[&root];
+        watched.insert(root.clone(), (inode, Some(UNIX_EPOCH)));
+        assert_eq!(
+            sync(&mut watcher, &mut watched, &roots),
+            (
+                true,
+                vec![("unwatch", root.clone()), ("watch", root.clone())]
+            )
+        );
+        assert_eq!(watched[&root], root_id(&root).unwrap());
+    }
+
+    #[test]
+    fn drops_roots_no_longer_configured() {
+        let dir = tempfile::tempdir().unwrap();
+        let kept = dir.path().join("kept");
+        let dropped = dir.path().join("dropped");
+        fs::create_dir(&kept).unwrap();
+        fs::create_dir(&dropped).unwrap();
+        let mut watcher = FakeWatcher::default();
+        let mut watched = WatchedRoots::new();
+        sync(&mut watcher, &mut watched, &[kept.clone(), dropped.clone()]);
+
+        assert_eq!(
+            sync(&mut watcher, &mut watched, std::slice::from_ref(&kept)),
+            (true, vec![("unwatch", dropped)])
+        );
+        assert_eq!(watched.keys().collect::<Vec<_>>(), [&kept]);
+    }
+
+    #[test]
+    fn failed_watch_is_retried_next_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("projects");
+        fs::create_dir(&root).unwrap();
+        let roots = [root.clone()];
+        let mut watcher = FakeWatcher {
+            fail_on: Some(root.clone()),
+            ..FakeWatcher::default()
+        };
+        let mut watched = WatchedRoots::new();
+
+        assert!(sync_roots(&mut watcher, &mut watched, &roots).is_err());
+        assert!(watched.is_empty());
+
+        watcher.fail_on = None;
+        assert_eq!(
+            sync(&mut watcher, &mut watched, &roots),
+            (true, vec![("watch", root)])
+        );
+    }
+}
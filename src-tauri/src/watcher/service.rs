@@ -0,0 +1,349 @@
+use notify_debouncer_mini::{
+    new_debouncer,
+    notify::{self, RecommendedWatcher, RecursiveMode, Watcher},
+    DebounceEventResult, Debouncer,
+};
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::poll::Poller;
+use super::roots::{sync_roots, WatchedRoots};
+use crate::alerts::check_budgets;
+use crate::cache::{CacheDelta, EntryCache};
+use crate::parser::types::{BillingWindow, UsageData};
+use crate::pricing::{is_pricing_file, reload_user_pricing, user_pricing_path};
+use crate::settings::{SettingsStore, WatchMode};
+use crate::tray::{refresh_tray, today_usage};
+
+const DEBOUNCE_DURATION_MS: u64 = 500;
+
+/// How often log roots are checked for appearing, vanishing or being
+/// recreated, since a watch can't be placed on a directory that isn't there.
+/// When polling, files are checked this often too.
+const TICK_INTERVAL_SECS: u64 = 3;
+
+/// How often native watching is checked for changes it never reported
+const EVENT_CHECK_INTERVAL_SECS: u64 = 15;
+
+/// A file modified this long ago should have had its event delivered
+const MISSED_EVENT_GRACE_SECS: u64 = 3;
+
+/// Event checks in a row that must find missed changes before native
+/// watching is given up. A single miss, such as an event dropped while the
+/// system was busy, is caught up on instead.
+const MISSED_CHECKS_BEFORE_POLLING: u32 = 3;
+
+/// How often appended entries are written to the cache snapshot, so a crash
+/// or force-quit doesn't lose them and re-parse the files on the next launch
+const SNAPSHOT_INTERVAL_SECS: u64 = 60;
+
+/// Payload of the `files-changed` event: what changed, and the totals the
+/// dashboard shows first, so it can patch its state instead of reloading
+#[derive(Debug, Clone, Serialize)]
+pub struct FilesChanged {
+    #[serde(rename = "newEntries")]
+    pub new_entries: usize,
+    #[serde(rename = "sessionIds")]
+    pub session_ids: Vec<String>,
+    #[serde(rename = "projectPaths")]
+    pub project_paths: Vec<String>,
+    /// Entries were removed or repriced, so everything should be reloaded
+    pub reset: bool,
+    pub today: UsageData,
+    #[serde(rename = "activeBlock")]
+    pub active_block: Option<BillingWindow>,
+}
+
+/// Tell the dashboard what `delta` changed, with today's updated totals
+pub fn emit_files_changed(app: &AppHandle, delta: CacheDelta) {
+    let Some(window) = app.get_webview_window("dashboard") else {
+        return;
+    };
+
+    let settings = app.state::<SettingsStore>().get();
+    let today = today_usage(app, &settings);
+    let payload = FilesChanged {
+        new_entries: delta.new_entries,
+        session_ids: delta.session_ids.into_iter().collect(),
+        project_paths: delta.project_paths.into_iter().collect(),
+        reset: delta.reset,
+        today: today.usage,
+        active_block: today.active,
+    };
+    let _ = window.emit("files-changed", payload);
+}
+
+enum WatchEvent {
+    LogsChanged(Vec<PathBuf>),
+    PricingChanged,
+    Tick,
+}
+
+fn start_debouncer(tx: Sender<WatchEvent>) -> notify::Result<Debouncer<RecommendedWatcher>> {
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(DEBOUNCE_DURATION_MS),
+        move |result: DebounceEventResult| {
+            if let Ok(events) = result {
+                let jsonl_paths: Vec<PathBuf> = events
+                    .iter()
+                    .filter(|e| e.path.extension().is_some_and(|ext| ext == "jsonl"))
+                    .map(|e| e.path.clone())
+                    .collect();
+
+                if !jsonl_paths.is_empty() {
+                    let _ = tx.send(WatchEvent::LogsChanged(jsonl_paths));
+                }
+
+                if events.iter().any(|e| is_pricing_file(&e.path)) {
+                    let _ = tx.send(WatchEvent::PricingChanged);
+                }
+            }
+        },
+    )?;
+
+    // Watch the config directory so pricing edits apply without a restart
+    if let Some(config_dir) = user_pricing_path().and_then(|p| p.parent().map(PathBuf::from)) {
+        let watched = std::fs::create_dir_all(&config_dir).and_then(|_| {
+            debouncer
+                .watcher()
+                .watch(&config_dir, RecursiveMode::NonRecursive)
+                .map_err(std::io::Error::other)
+        });
+        if let Err(e) = watched {
+            eprintln!("Failed to watch config directory {:?}: {:?}", config_dir, e);
+        }
+    }
+
+    Ok(debouncer)
+}
+
+/// Cached files modified a while ago that the cache never caught up with,
+/// which only happens when their events didn't arrive
+fn missed_changes(cache: &EntryCache) -> Vec<PathBuf> {
+    let grace = Duration::from_secs(MISSED_EVENT_GRACE_SECS);
+    cache
+        .stale_paths()
+        .into_iter()
+        .filter(|path| {
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age >= grace)
+        })
+        .collect()
+}
+
+/// Costs are computed at query time, so new prices need no rescan
+fn reload_pricing() -> Option<CacheDelta> {
+    if let Err(e) = reload_user_pricing() {
+        eprintln!("Ignoring pricing file: {}", e);
+        return None;
+    }
+    Some(CacheDelta::everything())
+}
+
+/// Filesystem events from notify, and the roots they cover
+struct NativeWatcher {
+    debouncer: Debouncer<RecommendedWatcher>,
+    roots: WatchedRoots,
+    last_event_check: Instant,
+    /// Consecutive event checks that found missed changes
+    missed_checks: u32,
+}
+
+impl NativeWatcher {
+    fn start(tx: Sender<WatchEvent>, roots: &[PathBuf]) -> Result<Self, String> {
+        let debouncer =
+            start_debouncer(tx).map_err(|e| format!("failed to create file watcher: {:?}", e))?;
+        let mut native = Self {
+            debouncer,
+            roots: WatchedRoots::new(),
+            last_event_check: Instant::now(),
+            missed_checks: 0,
+        };
+        native.sync(roots)?;
+        Ok(native)
+    }
+
+    fn sync(&mut self, roots: &[PathBuf]) -> Result<bool, String> {
+        sync_roots(self.debouncer.watcher(), &mut self.roots, roots)
+            .map_err(|e| format!("failed to watch log directory: {:?}", e))
+    }
+
+    /// Follow root changes and make sure events are still arriving,
+    /// catching up on files that were missed. Returns what changed in the
+    /// cache, or why native watching should be dropped.
+    fn check(
+        &mut self,
+        roots: &[PathBuf],
+        cache: &EntryCache,
+    ) -> Result<Option<CacheDelta>, String> {
+        if self.sync(roots)? {
+            // Files written before the watch was attached were missed
+            return Ok(Some(cache.initialize(roots)));
+        }
+
+        if self.last_event_check.elapsed() < Duration::from_secs(EVENT_CHECK_INTERVAL_SECS) {
+            return Ok(None);
+        }
+        self.last_event_check = Instant::now();
+
+        let missed = missed_changes(cache);
+        let Some(path) = missed.first() else {
+            self.missed_checks = 0;
+            return Ok(None);
+        };
+        self.missed_checks += 1;
+        if self.missed_checks >= MISSED_CHECKS_BEFORE_POLLING {
+            return Err(format!(
+                "no change event arrived for {:?}, {} checks in a row",
+                path, self.missed_checks
+            ));
+        }
+        eprintln!("No change event arrived for {:?}, catching up", path);
+        Ok(Some(cache.invalidate_paths(&missed)))
+    }
+}
+
+/// How log changes are noticed
+enum Source {
+    Native(NativeWatcher),
+    Poll {
+        poller: Poller,
+        /// Roots the cache was last scanned from
+        roots: Vec<PathBuf>,
+    },
+}
+
+impl Source {
+    fn polling(roots: Vec<PathBuf>) -> Self {
+        eprintln!("Polling for log changes every {}s", TICK_INTERVAL_SECS);
+        Source::Poll {
+            poller: Poller::new(),
+            roots,
+        }
+    }
+
+    /// Follow the log roots and, when polling, look for changed files.
+    /// Returns what changed in the cache, if anything.
+    fn tick(&mut self, app: &AppHandle) -> Option<CacheDelta> {
+        let settings = app.state::<SettingsStore>().get();
+        let roots = settings.log_roots().found;
+        let cache = app.state::<EntryCache>();
+
+        let delta = match self {
+            Source::Native(native) => {
+                let checked = match settings.watch_mode {
+                    WatchMode::Auto => native.check(&roots, &cache),
+                    WatchMode::Poll => Err("polling was selected in settings".to_string()),
+                };
+                match checked {
+                    Ok(None) => return None,
+                    Ok(Some(delta)) => delta,
+                    Err(reason) => {
+                        eprintln!("Switching to polling: {}", reason);
+                        *self = Source::polling(roots.clone());
+                        // Catch up on whatever the events missed
+                        cache.initialize(&roots)
+                    }
+                }
+            }
+            Source::Poll {
+                poller,
+                roots: scanned,
+            } => {
+                let pricing = if poller.pricing_changed() {
+                    reload_pricing()
+                } else {
+                    None
+                };
+
+                let delta = if *scanned != roots {
+                    scanned.clone_from(&roots);
+                    cache.initialize(&roots)
+                } else {
+                    let changed = poller.changed_logs(&roots, &cache);
+                    if changed.is_empty() {
+                        return pricing;
+                    }
+                    cache.invalidate_paths(&changed)
+                };
+                // New prices change every entry, which covers the new ones
+                pricing.unwrap_or(delta)
+            }
+        };
+
+        check_budgets(app);
+        Some(delta)
+    }
+}
+
+pub fn start_file_watcher(app_handle: AppHandle) {
+    std::thread::spawn(move || {
+        let (tx, rx) = mpsc::channel::<WatchEvent>();
+
+        let ticker = tx.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(Duration::from_secs(TICK_INTERVAL_SECS));
+            if ticker.send(WatchEvent::Tick).is_err() {
+                break;
+            }
+        });
+
+        // The cache was filled from these roots at startup, so attaching the
+        // first watches needs no rescan
+        let settings = app_handle.state::<SettingsStore>().get();
+        let roots = settings.log_roots().found;
+        let mut source = match settings.watch_mode {
+            WatchMode::Auto => match NativeWatcher::start(tx, &roots) {
+                Ok(native) => Source::Native(native),
+                Err(reason) => {
+                    eprintln!("Switching to polling: {}", reason);
+                    Source::polling(roots)
+                }
+            },
+            WatchMode::Poll => Source::polling(roots),
+        };
+        let mut last_snapshot = Instant::now();
+
+        loop {
+            let delta = match rx.recv() {
+                Ok(WatchEvent::LogsChanged(changed_paths)) => {
+                    // Refresh cache for changed files only
+                    let cache = app_handle.state::<EntryCache>();
+                    let delta = cache.invalidate_paths(&changed_paths);
+
+                    check_budgets(&app_handle);
+                    delta
+                }
+                Ok(WatchEvent::PricingChanged) => match reload_pricing() {
+                    Some(delta) => delta,
+                    None => continue,
+                },
+                Ok(WatchEvent::Tick) => {
+                    let delta = source.tick(&app_handle);
+                    if last_snapshot.elapsed() >= Duration::from_secs(SNAPSHOT_INTERVAL_SECS) {
+                        last_snapshot = Instant::now();
+                        // Only writes if something changed since the last save
+                        app_handle.state::<EntryCache>().save_snapshot();
+                    }
+                    match delta {
+                        Some(delta) => delta,
+                        None => continue,
+                    }
+                }
+                Err(_) => break,
+            };
+
+            refresh_tray(&app_handle);
+
+            // Then notify frontend
+            emit_files_changed(&app_handle, delta);
+        }
+    });
+}
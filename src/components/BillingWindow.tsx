@@ -1,46 +1,72 @@
 import { useState, useEffect } from "react";
-import type { BillingWindow as BillingWindowType, PlanType } from "../lib/types";
-import { PLAN_CONFIG } from "../lib/types";
+import { invoke } from "@tauri-apps/api/core";
+import type { BillingWindow as BillingWindowType, Plan, PlanType, Settings } from "../lib/types";
+import { PLAN_NAMES } from "../lib/types";
 import { formatCurrency, formatDuration, formatTime, formatTokens } from "../lib/formatters";
 
-const PLAN_STORAGE_KEY = "claude-usage-plan";
+const AUTO_PLAN = "auto";
+const DEFAULT_CUSTOM_TOKEN_LIMIT = 45_000_000;
 
 interface Props {
   windows: BillingWindowType[];
+  onSettingsChange: () => void;
 }
 
-export default function BillingWindow({ windows }: Props) {
-  const [plan, setPlan] = useState<PlanType>("max200");
+export default function BillingWindow({ windows, onSettingsChange }: Props) {
+  const [settings, setSettings] = useState<Settings | null>(null);
 
   useEffect(() => {
-    const stored = localStorage.getItem(PLAN_STORAGE_KEY) as PlanType | null;
-    if (stored && stored in PLAN_CONFIG) {
-      setPlan(stored);
-    }
+    invoke<Settings>("get_settings")
+      .then(setSettings)
+      .catch((e) => console.error("Failed to load settings:", e));
   }, []);
 
-  const handlePlanChange = (newPlan: PlanType) => {
-    setPlan(newPlan);
-    localStorage.setItem(PLAN_STORAGE_KEY, newPlan);
+  const activeWindow = windows.find((w) => w.isActive);
+
+  const savePlan = async (plan: Plan | null) => {
+    if (!settings) {
+      return;
+    }
+    try {
+      const saved = await invoke<Settings>("update_settings", {
+        newSettings: { ...settings, plan },
+      });
+      setSettings(saved);
+      onSettingsChange();
+    } catch (e) {
+      console.error("Failed to save settings:", e);
+    }
   };
 
-  const activeWindow = windows.find((w) => w.isActive);
+  const handlePlanChange = (value: PlanType | typeof AUTO_PLAN) => {
+    if (value === AUTO_PLAN) {
+      savePlan(null);
+    } else if (value === "custom") {
+      // Start from the cap currently in use so the block doesn't jump
+      const tokenLimit = activeWindow?.limit?.tokenLimit ?? DEFAULT_CUSTOM_TOKEN_LIMIT;
+      savePlan({ type: "custom", tokenLimit, costLimitUsd: null });
+    } else {
+      savePlan({ type: value });
+    }
+  };
+
+  const handleCustomLimitChange = (tokenLimit: number) => {
+    if (settings?.plan?.type === "custom" && tokenLimit > 0) {
+      savePlan({ ...settings.plan, tokenLimit });
+    }
+  };
 
   if (!activeWindow) {
     return null;
   }
 
-  const tokenLimit = PLAN_CONFIG[plan].tokenLimit;
-
   const timeProgressPercent = Math.max(
     0,
     Math.min(100, ((300 - activeWindow.remainingMinutes) / 300) * 100)
   );
 
-  const tokenProgressPercent = Math.max(
-    0,
-    Math.min(100, (activeWindow.totalTokens / tokenLimit) * 100)
-  );
+  const limitLabel = activeWindow.limit?.source === "historical" ? "of best block" : "of limit";
+  const plan = settings?.plan ?? null;
 
   return (
     <div className="bg-amber-50 dark:bg-amber-900/20 rounded-lg p-3">
@@ -67,22 +93,58 @@ export default function BillingWindow({ windows }: Props) {
 
       <div className="flex items-center justify-between text-xs text-amber-600 dark:text-amber-400 mb-2">
         <span>{formatTokens(activeWindow.totalTokens)} tokens</span>
-        <span className="font-medium">{tokenProgressPercent.toFixed(1)}% of limit</span>
+        {activeWindow.percentUsed !== null && (
+          <span className="font-medium">
+            {activeWindow.percentUsed.toFixed(1)}% {limitLabel}
+          </span>
+        )}
       </div>
 
+      {activeWindow.predictedLimitTime && (
+        <div className="text-xs text-red-600 dark:text-red-400 mb-2">
+          Limit reached around {formatTime(activeWindow.predictedLimitTime)} at this pace
+        </div>
+      )}
+
+      {activeWindow.burnRate && activeWindow.projection && (
+        <div className="flex items-center justify-between text-xs text-amber-600 dark:text-amber-400 mb-2">
+          <span>
+            {formatTokens(Math.round(activeWindow.burnRate.tokensPerMinute))}/min ·{" "}
+            {formatCurrency(activeWindow.burnRate.costPerHour)}/h
+          </span>
+          <span>
+            Projected {formatTokens(activeWindow.projection.totalTokens)} ·{" "}
+            {formatCurrency(activeWindow.projection.costUsd)}
+          </span>
+        </div>
+      )}
+
       <div className="flex items-center justify-between text-xs pt-2 border-t border-amber-200 dark:border-amber-700">
         <span className="text-amber-600 dark:text-amber-400">Plan:</span>
-        <select
-          value={plan}
-          onChange={(e) => handlePlanChange(e.target.value as PlanType)}
-          className="text-xs bg-amber-100 dark:bg-amber-800 text-amber-700 dark:text-amber-300 rounded px-1.5 py-0.5 border border-amber-300 dark:border-amber-600 cursor-pointer"
-        >
-          {(Object.keys(PLAN_CONFIG) as PlanType[]).map((key) => (
-            <option key={key} value={key}>
-              {PLAN_CONFIG[key].name}
-            </option>
-          ))}
-        </select>
+        <div className="flex items-center gap-1">
+          {plan?.type === "custom" && (
+            <input
+              type="number"
+              min={1}
+              defaultValue={plan.tokenLimit ?? undefined}
+              onBlur={(e) => handleCustomLimitChange(Number(e.target.value))}
+              title="Tokens per block"
+              className="w-24 text-xs bg-amber-100 dark:bg-amber-800 text-amber-700 dark:text-amber-300 rounded px-1.5 py-0.5 border border-amber-300 dark:border-amber-600"
+            />
+          )}
+          <select
+            value={plan?.type ?? AUTO_PLAN}
+            onChange={(e) => handlePlanChange(e.target.value as PlanType | typeof AUTO_PLAN)}
+            className="text-xs bg-amber-100 dark:bg-amber-800 text-amber-700 dark:text-amber-300 rounded px-1.5 py-0.5 border border-amber-300 dark:border-amber-600 cursor-pointer"
+          >
+            <option value={AUTO_PLAN}>Auto (best block)</option>
+            {(Object.keys(PLAN_NAMES) as PlanType[]).map((key) => (
+              <option key={key} value={key}>
+                {PLAN_NAMES[key]}
+              </option>
+            ))}
+          </select>
+        </div>
       </div>
     </div>
   );
@@ -1,11 +1,35 @@
+import { useState, useEffect, useRef } from "react";
 import type { UsageData } from "../lib/types";
-import { formatCurrency, formatTokens } from "../lib/formatters";
+import {
+  formatCostEstimate,
+  formatCurrency,
+  formatTokens,
+  unpricedModelsTitle,
+} from "../lib/formatters";
+
+/** How long the "+$0.42" badge stays after new spend arrives */
+const NEW_SPEND_BADGE_MS = 2500;
 
 interface Props {
   data: UsageData;
 }
 
 export default function DailySummary({ data }: Props) {
+  const previousCost = useRef(data.totalCostUsd);
+  const [newSpend, setNewSpend] = useState<number | null>(null);
+
+  useEffect(() => {
+    const added = data.totalCostUsd - previousCost.current;
+    previousCost.current = data.totalCostUsd;
+    if (added < 0.005) {
+      return;
+    }
+
+    setNewSpend(added);
+    const timer = setTimeout(() => setNewSpend(null), NEW_SPEND_BADGE_MS);
+    return () => clearTimeout(timer);
+  }, [data.totalCostUsd]);
+
   const totalTokens =
     data.totalTokens.inputTokens +
     data.totalTokens.outputTokens +
@@ -14,11 +38,19 @@ export default function DailySummary({ data }: Props) {
 
   return (
     <div className="bg-blue-50 dark:bg-blue-900/20 rounded-lg p-3">
-      <div className="text-xs text-blue-600 dark:text-blue-400 font-medium mb-1">
-        Today
+      <div className="flex items-center justify-between text-xs text-blue-600 dark:text-blue-400 font-medium mb-1">
+        <span>Today</span>
+        {newSpend !== null && (
+          <span className="text-green-600 dark:text-green-400 animate-pulse">
+            +{formatCurrency(newSpend)}
+          </span>
+        )}
       </div>
-      <div className="text-xl font-bold text-blue-700 dark:text-blue-300">
-        {formatCurrency(data.totalCostUsd)}
+      <div
+        className="text-xl font-bold text-blue-700 dark:text-blue-300"
+        title={unpricedModelsTitle(data)}
+      >
+        {formatCostEstimate(data)}
       </div>
       <div className="text-xs text-blue-500 dark:text-blue-400 mt-1">
         {formatTokens(totalTokens)} tokens
@@ -1,12 +1,27 @@
+import { useState, useEffect } from "react";
+import { listen } from "@tauri-apps/api/event";
 import { useUsageData } from "../hooks/useUsageData";
 import DailySummary from "./DailySummary";
 import WeeklySummary from "./WeeklySummary";
 import ModelBreakdown from "./ModelBreakdown";
 import BillingWindow from "./BillingWindow";
 import SessionList from "./SessionList";
+import SettingsPanel from "./SettingsPanel";
 
 export default function Dashboard() {
   const { data, loading, error, refresh } = useUsageData();
+  const [showSettings, setShowSettings] = useState(false);
+
+  // "Settings…" in the tray menu opens the dashboard on this panel
+  useEffect(() => {
+    const unlistenOpenSettings = listen("open-settings", () => {
+      setShowSettings(true);
+    });
+
+    return () => {
+      unlistenOpenSettings.then((fn) => fn());
+    };
+  }, []);
 
   if (loading && !data) {
     return (
@@ -44,20 +59,32 @@ export default function Dashboard() {
         <h1 className="text-base font-semibold text-neutral-800 dark:text-neutral-100">
           Claude Usage
         </h1>
-        {loading && (
-          <span className="text-xs text-neutral-400 dark:text-neutral-500">
-            Updating...
-          </span>
-        )}
+        <div className="flex items-center gap-2">
+          {loading && (
+            <span className="text-xs text-neutral-400 dark:text-neutral-500">
+              Updating...
+            </span>
+          )}
+          <button
+            onClick={() => setShowSettings(!showSettings)}
+            className="text-xs text-neutral-500 hover:text-neutral-600 dark:text-neutral-400"
+          >
+            Settings
+          </button>
+        </div>
       </header>
 
+      {showSettings && (
+        <SettingsPanel onClose={() => setShowSettings(false)} onSaved={refresh} />
+      )}
+
       <div className="grid grid-cols-2 gap-2">
         <DailySummary data={data.today} />
         <WeeklySummary data={data.week} />
       </div>
 
       {data.billingWindows.length > 0 && (
-        <BillingWindow windows={data.billingWindows} />
+        <BillingWindow windows={data.billingWindows} onSettingsChange={refresh} />
       )}
 
       <ModelBreakdown
@@ -0,0 +1,190 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { Budgets, ParseDiagnostics, Settings, TrayTitle } from "../lib/types";
+
+const TRAY_TITLE_NAMES: Record<TrayTitle, string> = {
+  off: "Icon only",
+  cost: "Today's cost",
+  percent: "Block % used",
+  timeLeft: "Block time left",
+};
+
+const BUDGET_FIELDS: { key: "dailyUsd" | "weeklyUsd" | "monthlyUsd"; label: string }[] = [
+  { key: "dailyUsd", label: "Daily" },
+  { key: "weeklyUsd", label: "Weekly" },
+  { key: "monthlyUsd", label: "Monthly" },
+];
+
+const inputClass =
+  "w-20 text-xs bg-white dark:bg-neutral-800 text-neutral-700 dark:text-neutral-200 rounded px-1.5 py-0.5 border border-neutral-300 dark:border-neutral-600";
+
+interface Props {
+  onClose: () => void;
+  onSaved: () => void;
+}
+
+function parseAmount(value: string): number | null {
+  return value.trim() === "" ? null : Number(value);
+}
+
+export default function SettingsPanel({ onClose, onSaved }: Props) {
+  const [settings, setSettings] = useState<Settings | null>(null);
+  const [missingRoots, setMissingRoots] = useState<string[]>([]);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    invoke<Settings>("get_settings")
+      .then(setSettings)
+      .catch((e) => setError(String(e)));
+    invoke<ParseDiagnostics>("get_parse_diagnostics")
+      .then((diagnostics) => setMissingRoots(diagnostics.missingRoots))
+      .catch(() => setMissingRoots([]));
+  }, []);
+
+  if (!settings) {
+    return error ? <div className="text-red-500 text-xs">{error}</div> : null;
+  }
+
+  const setBudgets = (budgets: Partial<Budgets>) =>
+    setSettings({ ...settings, budgets: { ...settings.budgets, ...budgets } });
+
+  const save = async () => {
+    try {
+      const saved = await invoke<Settings>("update_settings", { newSettings: settings });
+      setSettings(saved);
+      setError(null);
+      onSaved();
+      onClose();
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  return (
+    <div className="bg-neutral-50 dark:bg-neutral-800/50 rounded-lg p-3 space-y-2 text-xs text-neutral-600 dark:text-neutral-300">
+      <div className="flex items-center justify-between">
+        <span className="font-medium">Tray title</span>
+        <select
+          value={settings.trayTitle}
+          onChange={(e) => setSettings({ ...settings, trayTitle: e.target.value as TrayTitle })}
+          className={`${inputClass} w-32 cursor-pointer`}
+        >
+          {(Object.keys(TRAY_TITLE_NAMES) as TrayTitle[]).map((key) => (
+            <option key={key} value={key}>
+              {TRAY_TITLE_NAMES[key]}
+            </option>
+          ))}
+        </select>
+      </div>
+
+      {BUDGET_FIELDS.map(({ key, label }) => (
+        <div key={key} className="flex items-center justify-between">
+          <span>{label} budget ($)</span>
+          <input
+            type="number"
+            min={0}
+            step="any"
+            placeholder="None"
+            value={settings.budgets[key] ?? ""}
+            onChange={(e) => setBudgets({ [key]: parseAmount(e.target.value) })}
+            className={inputClass}
+          />
+        </div>
+      ))}
+
+      <div className="flex items-center justify-between">
+        <span>Alert at (% of budget)</span>
+        <input
+          type="text"
+          defaultValue={settings.budgets.thresholds.join(", ")}
+          onBlur={(e) =>
+            setBudgets({
+              thresholds: e.target.value
+                .split(",")
+                .map((p) => Number(p.trim()))
+                .filter((p) => Number.isInteger(p)),
+            })
+          }
+          className={`${inputClass} w-32`}
+        />
+      </div>
+
+      <div className="flex items-center justify-between">
+        <span>Icon amber / red at (%)</span>
+        <div className="flex items-center gap-1">
+          <input
+            type="number"
+            min={1}
+            value={settings.iconThresholds.warningPercent}
+            onChange={(e) =>
+              setSettings({
+                ...settings,
+                iconThresholds: { ...settings.iconThresholds, warningPercent: Number(e.target.value) },
+              })
+            }
+            className={`${inputClass} w-14`}
+          />
+          <input
+            type="number"
+            min={1}
+            value={settings.iconThresholds.criticalPercent}
+            onChange={(e) =>
+              setSettings({
+                ...settings,
+                iconThresholds: { ...settings.iconThresholds, criticalPercent: Number(e.target.value) },
+              })
+            }
+            className={`${inputClass} w-14`}
+          />
+        </div>
+      </div>
+
+      <div className="space-y-1">
+        <span>Extra log folders (one per line)</span>
+        <textarea
+          rows={2}
+          defaultValue={settings.extraLogDirs.join("\n")}
+          onBlur={(e) =>
+            setSettings({
+              ...settings,
+              extraLogDirs: e.target.value
+                .split("\n")
+                .map((dir) => dir.trim())
+                .filter((dir) => dir !== ""),
+            })
+          }
+          className={`${inputClass} w-full font-mono`}
+        />
+        {missingRoots.map((dir) => (
+          <div key={dir} className="text-amber-600 dark:text-amber-400 font-mono break-all">
+            Not found: {dir}
+          </div>
+        ))}
+      </div>
+
+      <label className="flex items-center justify-between">
+        <span title="For network drives and containers that never report file changes">
+          Poll for log changes
+        </span>
+        <input
+          type="checkbox"
+          checked={settings.watchMode === "poll"}
+          onChange={(e) =>
+            setSettings({ ...settings, watchMode: e.target.checked ? "poll" : "auto" })
+          }
+        />
+      </label>
+
+      {error && <div className="text-red-500">{error}</div>}
+
+      <div className="flex justify-end gap-2 pt-2 border-t border-neutral-200 dark:border-neutral-700">
+        <button onClick={onClose} className="text-neutral-500 hover:text-neutral-600">
+          Cancel
+        </button>
+        <button onClick={save} className="text-blue-500 hover:text-blue-600 font-medium">
+          Save
+        </button>
+      </div>
+    </div>
+  );
+}
@@ -1,5 +1,5 @@
 import type { UsageData } from "../lib/types";
-import { formatCurrency, formatTokens } from "../lib/formatters";
+import { formatCostEstimate, formatTokens, unpricedModelsTitle } from "../lib/formatters";
 
 interface Props {
   data: UsageData;
@@ -17,8 +17,11 @@ export default function WeeklySummary({ data }: Props) {
       <div className="text-xs text-purple-600 dark:text-purple-400 font-medium mb-1">
         This Week
       </div>
-      <div className="text-xl font-bold text-purple-700 dark:text-purple-300">
-        {formatCurrency(data.totalCostUsd)}
+      <div
+        className="text-xl font-bold text-purple-700 dark:text-purple-300"
+        title={unpricedModelsTitle(data)}
+      >
+        {formatCostEstimate(data)}
       </div>
       <div className="text-xs text-purple-500 dark:text-purple-400 mt-1">
         {formatTokens(totalTokens)} tokens
@@ -1,7 +1,25 @@
 import { useState, useEffect, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
-import type { DashboardData, UsageData, BillingWindow, SessionSummary } from "../lib/types";
+import type {
+  DashboardData,
+  UsageData,
+  BillingWindow,
+  SessionSummary,
+  FilesChanged,
+} from "../lib/types";
+
+/** Swap in the backend's view of the active block, which may be a new one */
+function patchActiveBlock(
+  windows: BillingWindow[],
+  activeBlock: BillingWindow | null
+): BillingWindow[] {
+  if (!activeBlock) {
+    return windows.map((w) => (w.isActive ? { ...w, isActive: false } : w));
+  }
+  const others = windows.filter((w) => w.id !== activeBlock.id);
+  return [...others, activeBlock].sort((a, b) => a.startTime.localeCompare(b.startTime));
+}
 
 export function useUsageData() {
   const [data, setData] = useState<DashboardData | null>(null);
@@ -35,18 +53,50 @@ export function useUsageData() {
     }
   }, []);
 
+  // Totals the change event doesn't carry
+  const refreshHistory = useCallback(async () => {
+    try {
+      const [week, month, sessions] = await Promise.all([
+        invoke<UsageData>("get_usage_data", { timeRange: "week" }),
+        invoke<UsageData>("get_usage_data", { timeRange: "month" }),
+        invoke<SessionSummary[]>("get_session_breakdown_cmd"),
+      ]);
+      setData((prev) => prev && { ...prev, week, month, sessions });
+    } catch (e) {
+      console.error("Failed to fetch usage data:", e);
+    }
+  }, []);
+
   useEffect(() => {
     refresh();
 
-    // Listen for file changes from the backend watcher (real-time updates)
-    const unlistenFilesChanged = listen("files-changed", () => {
-      refresh();
+    // Listen for file changes from the backend watcher (real-time updates).
+    // Today and the active block come with the event; the rest is fetched
+    // only when new entries arrived.
+    const unlistenFilesChanged = listen<FilesChanged>("files-changed", (event) => {
+      const change = event.payload;
+      if (change.reset) {
+        refresh();
+        return;
+      }
+
+      setData(
+        (prev) =>
+          prev && {
+            ...prev,
+            today: change.today,
+            billingWindows: patchActiveBlock(prev.billingWindows, change.activeBlock),
+          }
+      );
+      if (change.newEntries > 0) {
+        refreshHistory();
+      }
     });
 
     return () => {
       unlistenFilesChanged.then((fn) => fn());
     };
-  }, [refresh]);
+  }, [refresh, refreshHistory]);
 
   return { data, loading, error, refresh };
 }
@@ -1,3 +1,5 @@
+import type { UsageData } from "./types";
+
 export function formatCurrency(amount: number): string {
   return new Intl.NumberFormat("en-US", {
     style: "currency",
@@ -7,6 +9,19 @@ export function formatCurrency(amount: number): string {
   }).format(amount);
 }
 
+export function formatCostEstimate(data: UsageData): string {
+  const cost = formatCurrency(data.totalCostUsd);
+  return data.unpricedModels.length > 0 ? `~${cost}` : cost;
+}
+
+export function unpricedModelsTitle(data: UsageData): string | undefined {
+  if (data.unpricedModels.length === 0) {
+    return undefined;
+  }
+  const models = data.unpricedModels.map((m) => m.model).join(", ");
+  return `Estimated: no known price for ${models}`;
+}
+
 export function formatTokens(count: number): string {
   if (count >= 1_000_000) {
     return `${(count / 1_000_000).toFixed(1)}M`;
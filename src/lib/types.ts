@@ -2,6 +2,8 @@ export interface TokenUsage {
   inputTokens: number;
   outputTokens: number;
   cacheCreationInputTokens: number;
+  cacheCreation5mInputTokens: number;
+  cacheCreation1hInputTokens: number;
   cacheReadInputTokens: number;
 }
 
@@ -10,13 +12,36 @@ export interface ModelUsage extends TokenUsage {
   costUsd: number;
 }
 
+export interface UnpricedModel {
+  model: string;
+  tokens: TokenUsage;
+  estimatedCostUsd: number;
+}
+
 export interface UsageData {
   totalCostUsd: number;
   totalTokens: TokenUsage;
   modelBreakdown: ModelUsage[];
+  unpricedModels: UnpricedModel[];
   lastUpdated: string;
 }
 
+export type SeriesGranularity = "hour" | "day";
+
+export interface SeriesBucket {
+  startTime: string;
+  endTime: string;
+  label: string;
+  costUsd: number;
+  tokens: TokenUsage;
+  modelBreakdown: ModelUsage[];
+}
+
+export interface UsageSeries {
+  granularity: SeriesGranularity;
+  buckets: SeriesBucket[];
+}
+
 export interface BillingWindow {
   id: string;
   startTime: string;
@@ -25,6 +50,29 @@ export interface BillingWindow {
   costUsd: number;
   remainingMinutes: number;
   isActive: boolean;
+  isGap: boolean;
+  lastEntryTime: string | null;
+  burnRate: BurnRate | null;
+  projection: BlockProjection | null;
+  limit: UsageLimit | null;
+  percentUsed: number | null;
+  predictedLimitTime: string | null;
+}
+
+export interface UsageLimit {
+  tokenLimit: number | null;
+  costLimitUsd: number | null;
+  source: "plan" | "historical";
+}
+
+export interface BurnRate {
+  tokensPerMinute: number;
+  costPerHour: number;
+}
+
+export interface BlockProjection {
+  totalTokens: number;
+  costUsd: number;
 }
 
 export interface SessionSummary {
@@ -37,6 +85,15 @@ export interface SessionSummary {
   models: string[];
 }
 
+export interface ProjectSummary {
+  projectPath: string;
+  totalCostUsd: number;
+  tokens: TokenUsage;
+  sessionCount: number;
+  messageCount: number;
+  lastActivity: string;
+}
+
 export interface DashboardData {
   today: UsageData;
   week: UsageData;
@@ -45,10 +102,78 @@ export interface DashboardData {
   sessions: SessionSummary[];
 }
 
-export type PlanType = "pro" | "max100" | "max200";
+/** Payload of the backend's "files-changed" event */
+export interface FilesChanged {
+  newEntries: number;
+  sessionIds: string[];
+  projectPaths: string[];
+  /** Entries were removed or repriced; reload everything */
+  reset: boolean;
+  today: UsageData;
+  activeBlock: BillingWindow | null;
+}
+
+export type PlanType = "pro" | "max5" | "max20" | "custom";
+
+export type Plan =
+  | { type: "pro" | "max5" | "max20" }
+  | { type: "custom"; tokenLimit: number | null; costLimitUsd: number | null };
 
-export const PLAN_CONFIG: Record<PlanType, { name: string; tokenLimit: number }> = {
-  pro: { name: "Pro ($20)", tokenLimit: 45_000_000 },
-  max100: { name: "Max ($100)", tokenLimit: 225_000_000 },
-  max200: { name: "Max ($200)", tokenLimit: 900_000_000 },
+export const PLAN_NAMES: Record<PlanType, string> = {
+  pro: "Pro ($20)",
+  max5: "Max 5x ($100)",
+  max20: "Max 20x ($200)",
+  custom: "Custom",
 };
+
+export interface Budgets {
+  dailyUsd: number | null;
+  weeklyUsd: number | null;
+  monthlyUsd: number | null;
+  thresholds: number[];
+}
+
+export type TrayTitle = "off" | "cost" | "percent" | "timeLeft";
+
+export interface IconThresholds {
+  warningPercent: number;
+  criticalPercent: number;
+}
+
+export type WatchMode = "auto" | "poll";
+
+export interface Settings {
+  plan: Plan | null;
+  budgets: Budgets;
+  trayTitle: TrayTitle;
+  iconThresholds: IconThresholds;
+  extraLogDirs: string[];
+  watchMode: WatchMode;
+}
+
+export type SkipReason = "invalidUtf8" | "invalidJson" | "invalidEntry" | "notAnEntry" | "noUsage";
+
+export interface ParseSample {
+  line: number;
+  reason: SkipReason;
+  error: string;
+  excerpt: string;
+}
+
+export interface ParseStats {
+  linesRead: number;
+  entriesKept: number;
+  skipped: Partial<Record<SkipReason, number>>;
+  samples: ParseSample[];
+}
+
+export interface FileDiagnostics {
+  path: string;
+  error: string | null;
+  stats: ParseStats;
+}
+
+export interface ParseDiagnostics {
+  missingRoots: string[];
+  files: FileDiagnostics[];
+}